@@ -0,0 +1,174 @@
+// Timings of the monads against the plain rust they stand in for.
+//
+//     cargo bench                 # everything
+//     cargo bench -- writer       # only benchmarks whose name contains `writer`
+//
+// Each benchmark is sampled a number of times and the median time per
+// iteration is reported, so that one noisy sample does not skew the result.
+use std::hint::black_box;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use monad_rs::prelude::*;
+use monad_rs::monads::inline::state::{RunState, modify};
+
+
+const SAMPLES: usize = 15;
+const TARGET: Duration = Duration::from_millis(20);
+
+
+struct Bencher {
+    filter: Option<String>,
+}
+
+impl Bencher {
+    fn from_args() -> Bencher {
+        Bencher { filter: std::env::args().skip(1).find(|a| !a.starts_with('-')) }
+    }
+
+    // calibrates the number of iterations so one sample takes about `TARGET`,
+    // then reports the median of `SAMPLES` samples.
+    fn run<T>(&self, name: &str, f: impl Fn() -> T) {
+        if let Some(filter) = &self.filter {
+            if !name.contains(filter.as_str()) { return }
+        }
+
+        let mut iters: u32 = 1;
+        loop {
+            let start = Instant::now();
+            for _ in 0..iters { black_box(f()); }
+            if start.elapsed() >= TARGET / 4 || iters >= 1 << 24 { break }
+            iters *= 2;
+        }
+        iters = (iters * 4).max(1);
+
+        let mut samples: Vec<Duration> = (0..SAMPLES)
+            .map(|_| {
+                let start = Instant::now();
+                for _ in 0..iters { black_box(f()); }
+                start.elapsed() / iters
+            })
+            .collect();
+        samples.sort();
+        println!("{:<40} {:>12?} / iter", name, samples[SAMPLES / 2]);
+    }
+}
+
+
+// state: counts up `depth` times.
+
+fn state_chain(depth: usize) -> StateMonad<i64, i64> {
+    let mut m: StateMonad<i64, i64> = state_unit(0);
+    for _ in 0..depth {
+        m = state_do!(
+            x <- m,
+            s <- get(),
+            put(s + 1),
+            state_unit(x + s)
+        );
+    }
+    m
+}
+
+fn state_by_hand(depth: usize, mut s: i64) -> (i64, i64) {
+    let mut x: i64 = 0;
+    for _ in 0..depth {
+        x += s;
+        s += 1;
+    }
+    (x, s)
+}
+
+
+// reader: sums the configuration `depth` times.
+
+fn reader_chain(depth: usize) -> ReaderMonad<i64, i64> {
+    let mut m: ReaderMonad<i64, i64> = reader_unit(0);
+    for _ in 0..depth {
+        m = reader_do!(
+            x <- m,
+            cfg <- load(),
+            reader_unit(x + cfg)
+        );
+    }
+    m
+}
+
+
+// writer: logs one line per step, into a string or a list of lines.
+
+fn writer_chain<Tlog: Monoid<T = Tlog> + Clone + 'static>(depth: i64, line: fn(i64) -> Tlog) -> WriterMonad<i64, Tlog> {
+    let step: WriterKleisli<i64, i64, Tlog> = WriterKleisli { kleisli: Rc::new(move |x: i64| Writer::new(x + 1, line(x))) };
+    let mut m: WriterMonad<i64, Tlog> = writer_unit(0);
+    for _ in 0..depth {
+        m = writer_bind(m, step.clone());
+    }
+    m
+}
+
+fn writer_by_hand(depth: i64) -> (i64, Vec<String>) {
+    let mut log: Vec<String> = vec![];
+    let mut x: i64 = 0;
+    for _ in 0..depth {
+        log.push(format!("step {}", x));
+        x += 1;
+    }
+    (x, log)
+}
+
+
+// cont: adds one `depth` times in continuation passing style.
+
+fn cont_chain(depth: usize) -> ContMonad<i64, i64> {
+    let mut m: ContMonad<i64, i64> = cont_unit(0);
+    for _ in 0..depth {
+        m = cont_do!(
+            x <- m,
+            cont_unit(x + 1)
+        );
+    }
+    m
+}
+
+fn cps_by_hand(depth: usize, k: &dyn Fn(i64) -> i64) -> i64 {
+    if depth == 0 { return k(0) }
+    cps_by_hand(depth - 1, &|x| k(x + 1))
+}
+
+
+fn main() {
+    let b: Bencher = Bencher::from_args();
+
+    for depth in [10, 100, 1000] {
+        let m = state_chain(depth);
+        b.run(&format!("state/do/{}", depth), || (m.run_state)(black_box(1)));
+        b.run(&format!("state/build+do/{}", depth), || (state_chain(depth).run_state)(black_box(1)));
+        let m = (0..depth).fold(modify(|s: i64| s).boxed(), |m, _| m.then(modify(|s: i64| s + 1)).boxed());
+        b.run(&format!("state/inline-boxed/{}", depth), || m.run(black_box(1)));
+        b.run(&format!("state/by-hand/{}", depth), || state_by_hand(depth, black_box(1)));
+    }
+    let m = modify(|s: i64| s + 1).then(modify(|s: i64| s + 1)).then(modify(|s: i64| s + 1))
+        .then(modify(|s: i64| s + 1)).then(modify(|s: i64| s + 1)).then(modify(|s: i64| s + 1))
+        .then(modify(|s: i64| s + 1)).then(modify(|s: i64| s + 1)).then(modify(|s: i64| s + 1))
+        .then(modify(|s: i64| s + 1));
+    b.run("state/inline/10", || m.run(black_box(1)));
+
+    for depth in [10, 100, 1000] {
+        let m = reader_chain(depth);
+        b.run(&format!("reader/do/{}", depth), || (m.run_reader)(&black_box(3)));
+        b.run(&format!("reader/by-hand/{}", depth), || (0..depth as i64).map(|_| black_box(3)).sum::<i64>());
+    }
+
+    for depth in [10, 100, 1000] {
+        b.run(&format!("writer/string/{}", depth), || writer_chain(depth, |x| format!("step {}", x)));
+        b.run(&format!("writer/vec/{}", depth), || writer_chain(depth, |x| vec![format!("step {}", x)]));
+        b.run(&format!("writer/by-hand/{}", depth), || writer_by_hand(black_box(depth)));
+    }
+
+    for depth in [10, 100, 1000] {
+        let m = cont_chain(depth);
+        b.run(&format!("cont/do/{}", depth), || cont_eval(m.clone()));
+        b.run(&format!("cont/by-hand/{}", depth), || cps_by_hand(black_box(depth), &|x| x));
+        b.run(&format!("cont/direct/{}", depth), || (0..black_box(depth) as i64).fold(0, |x, _| x + 1));
+    }
+}
@@ -0,0 +1,18 @@
+// Monads, in rust.
+//
+// Every monad lives in its own module under `monads` and can be switched off
+// through the cargo feature of the same name. `prelude` gathers the parts
+// most programs need, including the do-notation macros.
+//
+// The crate is `no_std`. The monads only need `Rc`, closures and strings, all
+// of which come from `alloc`; the `std` feature adds what needs an operating
+// system, such as `HashMap` monoids and `fold_map_par`.
+#![no_std]
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(any(feature = "std", test))]
+extern crate std;
+
+pub mod monads;
+pub mod prelude;
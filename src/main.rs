@@ -1,11 +1,4 @@
-use std::i64;
-use std::rc::Rc;
-
-mod monads;
-use crate::monads::monoid::Monoid;
-use crate::monads::writer::{WriterKleisli, WriterMonad, writer_unit, writer_fmap, writer_apply, writer_bind, compose_writers, log};
-use crate::monads::reader::{ReaderKleisli, ReaderMonad, reader_unit, reader_fmap, reader_apply, reader_bind, load};
-use crate::monads::state::{StateKleisli, StateMonad, state_unit, state_fmap, state_apply, state_bind, get, put};
+use monad_rs::prelude::*;
 
 
 #[derive(Debug, Clone)]
@@ -25,29 +18,29 @@ impl Monoid for StringLog {
 fn main() {
 
     fn add1_function(x: i64) -> WriterMonad<i64, StringLog> {
-        return (x + 1, StringLog { log: "added 1".to_string() })
+        Writer::new(x + 1, StringLog { log: "added 1".to_string() })
     }
     let add1 = WriterKleisli { kleisli: Rc::new(add1_function) };
 
     let times2 = WriterKleisli { kleisli: Rc::new(|x: i64| -> WriterMonad<i64, StringLog> {
-        (2 * x, StringLog { log: "multiplied by 2".to_string() })
+        Writer::new(2 * x, StringLog { log: "multiplied by 2".to_string() })
     })};
 
     let add1_times2: WriterKleisli<i64, i64, StringLog> = compose_writers(add1, times2);
 
     let val_log: WriterMonad<i64, StringLog> = writer_bind(writer_unit(10), add1_times2);
 
-    println!("{}", val_log.1.log);
+    println!("{}", val_log.log.log);
 
 
     let k0: WriterKleisli<i64, i64, StringLog> = WriterKleisli { kleisli: Rc::new(
-        |x: i64| -> WriterMonad<i64, StringLog> { (3*x, StringLog { log: "x3".to_string() }) }
+        |x: i64| -> WriterMonad<i64, StringLog> { Writer::new(3*x, StringLog { log: "x3".to_string() }) }
     ) };
     let k1 = k0.clone();
     let k2 = k0.clone();
     let k3 = k0.clone();
     let k4: WriterKleisli<i64, f64, StringLog> = WriterKleisli { kleisli: Rc::new(
-        |x: i64| -> WriterMonad<f64, StringLog> { ((x as f64) / 10., StringLog { log: "div10".to_string() }) }
+        |x: i64| -> WriterMonad<f64, StringLog> { Writer::new((x as f64) / 10., StringLog { log: "div10".to_string() }) }
     ) };
     let result = writer_binds!( { writer_unit(11) } >>= { k0 } >>= { k1 } >>= { k2 } >>= { k3 } >>= { k4 } );
     println!("{:?}", result);
@@ -70,7 +63,7 @@ fn main() {
     println!("{:?}", g(3, 8));
 
 
-    let square_value = |ma: (i64, StringLog) | writer_do!(
+    let square_value = |ma: WriterMonad<i64, StringLog>| writer_do!(
         val <- ma,
         val2 = val * val,
         log(StringLog{ log: "squared the number".to_string() }),
@@ -92,20 +85,20 @@ fn main() {
         cfg <- load(),
         reader_unit(4 + cfg.1)
     );
-    println!("{:?}", (result.run_reader)((0, 1, 2)));
+    println!("{:?}", (result.run_reader)(&(0, 1, 2)));
 
-    fn concat<T: Clone>(vec0: &Vec<T>, vec1: &Vec<T>) -> Vec<T> {
+    fn concat<T: Clone>(vec0: &[T], vec1: &[T]) -> Vec<T> {
         let mut ret = vec0.to_vec();
-        for i in vec1.into_iter() {
+        for i in vec1.iter() {
             ret.push(i.clone());
         }
-        return ret
+        ret
     }
 
-    let act_on_state = state_do!(
+    let act_on_state: StateMonad<Vec<i64>, i64> = state_do!(
         s <- get(),
         x = s[0],
-        new_s = concat(&s, &vec![x+1]),
+        new_s = concat(&s, &[x+1]),
         put(new_s),
         state_unit(x+10)
     );
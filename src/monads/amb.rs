@@ -0,0 +1,255 @@
+use core::mem;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+use super::cont::{ContMonad, RunCont, cont_unit};
+
+
+// McCarthy's `amb`: a computation that may take any of several values.
+// it is a continuation monad whose answer is the lazy stream of every
+// solution: `amb` runs the rest of the computation once per value and
+// joins the streams it gets back, and `fail` answers with none. nothing is
+// searched until a solution is asked for, and then only up to that solution.
+pub type Amb<Tr, Ta> = ContMonad<Stream<Tr>, Ta>;
+
+pub type Later<Tr> = Rc<dyn Fn() -> Stream<Tr>>;
+
+pub enum Stream<Tr> {
+    Nil,
+    Cons(Tr, Later<Tr>),
+    // nothing found yet, but there is more to search. each choice starts
+    // with one, so that searching goes one step at a time.
+    Delay(Later<Tr>)
+}
+
+
+// every solution of `s`, then every solution of `t`.
+pub fn append<Tr: 'static>(s: Stream<Tr>, t: Later<Tr>) -> Stream<Tr> {
+    match s {
+        Stream::Nil => t(),
+        Stream::Cons(x, f) => Stream::Cons(x, Rc::new(move || -> Stream<Tr> { append(f(), t.clone()) })),
+        Stream::Delay(f) => Stream::Delay(Rc::new(move || -> Stream<Tr> { append(f(), t.clone()) }))
+    }
+}
+
+// the solutions of `s` and `t` taking turns, one search step each, so that
+// a branch that searches forever does not starve the other.
+pub fn interleave<Tr: 'static>(s: Stream<Tr>, t: Later<Tr>) -> Stream<Tr> {
+    match s {
+        Stream::Nil => t(),
+        Stream::Cons(x, f) => Stream::Cons(x, Rc::new(move || -> Stream<Tr> { interleave(t(), f.clone()) })),
+        Stream::Delay(f) => Stream::Delay(Rc::new(move || -> Stream<Tr> { interleave(t(), f.clone()) }))
+    }
+}
+
+
+fn choice<Tr: 'static, Ta: 'static>(
+    join: fn(Stream<Tr>, Later<Tr>) -> Stream<Tr>,
+    ma: Amb<Tr, Ta>,
+    mb: Amb<Tr, Ta>
+) -> Amb<Tr, Ta> {
+    let (run_a, run_b): (RunCont<Stream<Tr>, Ta>, RunCont<Stream<Tr>, Ta>) = (ma.run_cont, mb.run_cont);
+    ContMonad { run_cont: Rc::new(move |k: Rc<dyn Fn(Ta) -> Stream<Tr>>| -> Stream<Tr> {
+        let (run_a, run_b): (RunCont<Stream<Tr>, Ta>, RunCont<Stream<Tr>, Ta>) = (run_a.clone(), run_b.clone());
+        Stream::Delay(Rc::new(move || -> Stream<Tr> {
+            let run_b: RunCont<Stream<Tr>, Ta> = run_b.clone();
+            let k_b: Rc<dyn Fn(Ta) -> Stream<Tr>> = k.clone();
+            join(run_a(k.clone()), Rc::new(move || -> Stream<Tr> { run_b(k_b.clone()) }))
+        }))
+    }) }
+}
+
+
+// the solutions of `ma`, then those of `mb`: depth first.
+pub fn either<Tr: 'static, Ta: 'static>(ma: Amb<Tr, Ta>, mb: Amb<Tr, Ta>) -> Amb<Tr, Ta> {
+    choice(append, ma, mb)
+}
+
+// the solutions of `ma` and `mb`, taking turns.
+pub fn either_fair<Tr: 'static, Ta: 'static>(ma: Amb<Tr, Ta>, mb: Amb<Tr, Ta>) -> Amb<Tr, Ta> {
+    choice(interleave, ma, mb)
+}
+
+
+// amb :: [a] -> Amb r a
+pub fn amb<Tr: 'static, Ta: Clone + 'static>(xs: impl IntoIterator<Item = Ta>) -> Amb<Tr, Ta> {
+    let alts: Vec<Amb<Tr, Ta>> = xs.into_iter().map(cont_unit).collect();
+    alts.into_iter().rev().fold(fail(), |rest: Amb<Tr, Ta>, m: Amb<Tr, Ta>| either(m, rest))
+}
+
+// the same, with the values' searches taking turns.
+pub fn amb_fair<Tr: 'static, Ta: Clone + 'static>(xs: impl IntoIterator<Item = Ta>) -> Amb<Tr, Ta> {
+    let alts: Vec<Amb<Tr, Ta>> = xs.into_iter().map(cont_unit).collect();
+    alts.into_iter().rev().fold(fail(), |rest: Amb<Tr, Ta>, m: Amb<Tr, Ta>| either_fair(m, rest))
+}
+
+// no solution: the search backtracks to the last choice.
+pub fn fail<Tr: 'static, Ta: 'static>() -> Amb<Tr, Ta> {
+    ContMonad { run_cont: Rc::new(|_: Rc<dyn Fn(Ta) -> Stream<Tr>>| -> Stream<Tr> { Stream::Nil }) }
+}
+
+pub fn require<Tr: 'static>(cond: bool) -> Amb<Tr, ()> {
+    if cond { cont_unit(()) } else { fail() }
+}
+
+
+// the solutions, searched for one at a time as they are asked for.
+pub struct Solutions<Tr> {
+    pending: Stream<Tr>
+}
+
+pub fn solutions<Tr: 'static>(ma: Amb<Tr, Tr>) -> Solutions<Tr> {
+    let found: Rc<dyn Fn(Tr) -> Stream<Tr>> = Rc::new(|a: Tr| -> Stream<Tr> { Stream::Cons(a, Rc::new(|| Stream::Nil)) });
+    Solutions { pending: Stream::Delay(Rc::new(move || -> Stream<Tr> { (ma.run_cont)(found.clone()) })) }
+}
+
+impl<Tr> Iterator for Solutions<Tr> {
+    type Item = Tr;
+
+    fn next(&mut self) -> Option<Tr> {
+        loop {
+            match mem::replace(&mut self.pending, Stream::Nil) {
+                Stream::Nil => return None,
+                Stream::Cons(x, rest) => {
+                    self.pending = Stream::Delay(rest);
+                    return Some(x)
+                },
+                Stream::Delay(f) => self.pending = f()
+            }
+        }
+    }
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use crate::cont_do;
+
+    #[test]
+    fn test_amb() {
+        let triples: Amb<(i64, i64, i64), (i64, i64, i64)> = cont_do!(
+            a <- amb(1..20),
+            b <- amb(a..20),
+            c <- amb(b..30),
+            require(a * a + b * b == c * c),
+            cont_unit((a, b, c))
+        );
+        assert_eq!(solutions(triples).take(3).collect::<Vec<(i64, i64, i64)>>(), vec![(3, 4, 5), (5, 12, 13), (6, 8, 10)]);
+
+        let none: Amb<i64, i64> = cont_do!(x <- amb(vec![1, 2, 3]), require(x > 3), cont_unit(x));
+        assert_eq!(solutions(none).next(), None);
+    }
+
+    // queens placed row by row, `cols[i]` being the column of the queen on row `i`.
+    fn with(cols: &[usize], c: usize) -> Vec<usize> {
+        let mut placed: Vec<usize> = cols.to_vec();
+        placed.push(c);
+        placed
+    }
+
+    // the last queen is not attacked by any of the others.
+    fn safe(placed: &[usize]) -> bool {
+        let (row, c): (usize, usize) = (placed.len() - 1, placed[placed.len() - 1]);
+        placed[..row].iter().enumerate().all(|(r, &q)| q != c && q.abs_diff(c) != row - r)
+    }
+
+    fn queens(n: usize, cols: Vec<usize>) -> Amb<Vec<usize>, Vec<usize>> {
+        if cols.len() == n { return cont_unit(cols) }
+        cont_do!(
+            placed <- amb((0..n).map(|c: usize| with(&cols, c))),
+            require(safe(&placed)),
+            queens(n, placed.clone())
+        )
+    }
+
+    #[test]
+    fn test_queens() {
+        let all: Vec<Vec<usize>> = solutions(queens(8, Vec::new())).collect();
+        assert_eq!(all.len(), 92);
+        assert_eq!(all[0], vec![0, 4, 7, 5, 2, 6, 1, 3]);
+
+        // asking for one solution only searches up to it.
+        assert_eq!(solutions(queens(12, Vec::new())).next().map(|q| q.len()), Some(12));
+    }
+
+    // a digit not taken yet, the digits taken being the bits set in `used`.
+    fn digit(used: u16, from: u16) -> Amb<[u16; 8], u16> {
+        amb((from..10).filter(move |d| used & (1 << d) == 0))
+    }
+
+    //   SEND
+    // + MORE
+    // ------
+    //  MONEY
+    //
+    // solved column by column from the right, so a wrong digit is given up
+    // as soon as its column does not add up.
+    fn send_more_money() -> Amb<[u16; 8], [u16; 8]> {
+        cont_do!(
+            d <- digit(0, 0),
+            e <- digit(1 << d, 0),
+            y = (d + e) % 10,
+            require(y != d && y != e),
+            used = (1 << d) | (1 << e) | (1 << y),
+            n <- digit(used, 0),
+            r <- digit(used | (1 << n), 0),
+            require((n + r + (d + e) / 10) % 10 == e),
+            c2 = (n + r + (d + e) / 10) / 10,
+            o <- digit(used | (1 << n) | (1 << r), 0),
+            require((e + o + c2) % 10 == n),
+            c3 = (e + o + c2) / 10,
+            s <- digit(used | (1 << n) | (1 << r) | (1 << o), 1),
+            m <- digit(used | (1 << n) | (1 << r) | (1 << o) | (1 << s), 1),
+            require(s + m + c3 == 10 * m + o),
+            cont_unit([s, e, n, d, m, o, r, y])
+        )
+    }
+
+    #[test]
+    fn test_send_more_money() {
+        let all: Vec<[u16; 8]> = solutions(send_more_money()).collect();
+        assert_eq!(all, vec![[9, 5, 6, 7, 1, 0, 8, 2]]);
+    }
+
+    // n, n + step, n + 2 step, ...
+    fn from<Tr: 'static>(n: i64, step: i64) -> Amb<Tr, i64> {
+        ContMonad { run_cont: Rc::new(move |k: Rc<dyn Fn(i64) -> Stream<Tr>>| -> Stream<Tr> {
+            (either(cont_unit(n), from(n + step, step)).run_cont)(k)
+        }) }
+    }
+
+    fn from_fair<Tr: 'static>(n: i64, step: i64) -> Amb<Tr, i64> {
+        ContMonad { run_cont: Rc::new(move |k: Rc<dyn Fn(i64) -> Stream<Tr>>| -> Stream<Tr> {
+            (either_fair(cont_unit(n), from_fair(n + step, step)).run_cont)(k)
+        }) }
+    }
+
+    #[test]
+    fn test_fair() {
+        let unfair: Amb<i64, i64> = either(from(0, 2), from(1, 2));
+        assert_eq!(solutions(unfair).take(6).collect::<Vec<i64>>(), vec![0, 2, 4, 6, 8, 10]);
+
+        let fair: Amb<i64, i64> = either_fair(from(0, 2), from(1, 2));
+        assert_eq!(solutions(fair).take(6).collect::<Vec<i64>>(), vec![0, 1, 2, 3, 4, 5]);
+
+        // a branch that searches forever without finding anything.
+        let never = || -> Amb<i64, i64> { cont_do!(_x <- from(0, 1), fail()) };
+        assert_eq!(solutions(either_fair(never(), cont_unit(7))).next(), Some(7));
+        let first_never: Amb<i64, i64> = cont_do!(
+            b <- amb_fair(vec![true, false]),
+            if b { never() } else { cont_unit(7) }
+        );
+        assert_eq!(solutions(first_never).next(), Some(7));
+
+        // pairs over two infinite ranges: every pair is reached eventually.
+        let pairs: Amb<(i64, i64), (i64, i64)> = cont_do!(
+            x <- from_fair(0, 1),
+            y <- from_fair(0, 1),
+            cont_unit((x, y))
+        );
+        assert!(solutions(pairs).take(200).any(|p| p == (3, 4)));
+    }
+}
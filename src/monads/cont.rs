@@ -1,9 +1,29 @@
-use std::rc::Rc;
+#[cfg(any(test, feature = "testing"))]
+use core::hash::Hash;
+use alloc::rc::Rc;
+
+#[cfg(any(test, feature = "testing"))]
+use super::observe::ObserveEq;
+#[cfg(any(test, feature = "testing"))]
+use super::prop::{Arbitrary, Fun};
+
+
+// a suspended computation: given what to do with an `a`, it produces the final `r`.
+pub type RunCont<Tr, Ta> = Rc<dyn Fn(Rc<dyn Fn(Ta) -> Tr>) -> Tr>;
 
 
 #[derive(Clone)]
 pub struct ContMonad<Tr, Ta> {
-    run_cont: Rc<dyn Fn(Rc<dyn Fn(Ta) -> Tr>) -> Tr>
+    pub run_cont: RunCont<Tr, Ta>
+}
+
+
+// continuation monads are observed by handing them an arbitrary continuation.
+#[cfg(any(test, feature = "testing"))]
+impl<Tr: Arbitrary + PartialEq + 'static, Ta: Hash + 'static> ObserveEq for ContMonad<Tr, Ta> {
+    type Input = Fun<Ta, Tr>;
+    type Output = Tr;
+    fn observe(&self, k: &Fun<Ta, Tr>) -> Self::Output { (self.run_cont)(k.to_rc()) }
 }
 
 
@@ -59,10 +79,10 @@ pub fn cont_apply<Tr: 'static, Ta: 'static, Tb: 'static>(
     mf: ContMonad<Tr, Rc<dyn Fn(Ta) -> Tb>>,
     ma: ContMonad<Tr, Ta>
 ) -> ContMonad<Tr, Tb> {
-    let f_abrr: Rc<dyn Fn(Rc<dyn Fn(Rc<dyn Fn(Ta) -> Tb>) -> Tr>) -> Tr> = mf.run_cont.clone();
+    let f_abrr: RunCont<Tr, Rc<dyn Fn(Ta) -> Tb>> = mf.run_cont.clone();
 
     ContMonad { run_cont: Rc::new( move |f_br: Rc<dyn Fn(Tb) -> Tr>| -> Tr {
-        let f_arr: Rc<dyn Fn(Rc<dyn Fn(Ta) -> Tr>) -> Tr> = ma.run_cont.clone();
+        let f_arr: RunCont<Tr, Ta> = ma.run_cont.clone();
 
         f_abrr(Rc::new(move |f_ab: Rc<dyn Fn(Ta) -> Tb>| -> Tr {
             let f_br_clone: Rc<dyn Fn(Tb) -> Tr> = f_br.clone();
@@ -105,39 +125,59 @@ pub fn cont_bind<Tr: 'static, Ta: 'static, Tb: 'static>(
 }
 
 
+// do-notation. `cont_do!(sync => ...)` builds the thread-safe variant
+// from `monads::sync::cont` instead, and `cont_do!(once => ...)` the
+// single-shot one from `monads::once::cont`. internally every rule carries
+// the module to take the monad from and the pointer its kleisli arrows are
+// stored in.
 #[macro_export]
 macro_rules! cont_do {
+    (sync => $($rest:tt)*) => { $crate::cont_do!(@[$crate::monads::sync::cont][$crate::monads::sync::Arc] $($rest)*) };
+    (once => $($rest:tt)*) => { $crate::cont_do!(@[$crate::monads::once::cont][$crate::monads::once::Box] $($rest)*) };
+
     // trailing comma
-    () => {};
+    (@[$($m:tt)*][$($p:tt)*]) => {};
 
-    ($v:ident = $e:expr,  $($rest:tt)*) => { (|$v| { cont_do!($($rest)*) })($e) };
+    (@[$($m:tt)*][$($p:tt)*] $v:ident = $e:expr,  $($rest:tt)*) => { (|$v| { $crate::cont_do!(@[$($m)*][$($p)*] $($rest)*) })($e) };
 
-    ($v:ident <- $e:expr, $($rest:tt)*) => {
-        cont_bind(
+    (@[$($m:tt)*][$($p:tt)*] $v:ident <- $e:expr, $($rest:tt)*) => {
+        $($m)*::cont_bind(
             $e,
-            ContKleisli {
-                kleisli: Rc::new( move |$v| { cont_do!($($rest)*) } )
+            $($m)*::ContKleisli {
+                kleisli: $($p)*::new( move |$v| { $crate::cont_do!(@[$($m)*][$($p)*] $($rest)*) } )
             }
         )
     };
 
-    ($e:expr, $($rest:tt)*) => {
-        cont_bind(
+    (@[$($m:tt)*][$($p:tt)*] $e:expr, $($rest:tt)*) => {
+        $($m)*::cont_bind(
             $e,
-            ContKleisli {
-                kleisli: Rc::new( move |_| { cont_do!($($rest)*) } )
+            $($m)*::ContKleisli {
+                kleisli: $($p)*::new( move |_| { $crate::cont_do!(@[$($m)*][$($p)*] $($rest)*) } )
             }
         )
     };
 
-    ($e:expr) => { $e };
+    (@[$($m:tt)*][$($p:tt)*] $e:expr) => { $e };
+
+    ($($rest:tt)*) => { $crate::cont_do!(@[$crate::monads::cont][$crate::prelude::Rc] $($rest)*) };
 }
 
 
+
 // tests
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::monads::laws::{check_functor_laws, check_monad_laws};
+    use crate::monads::prop::Config;
+
+    // passes two values on to the continuation, to make sure that
+    // continuations invoked more than once are threaded correctly.
+    fn gen_cont(p: &(i64, i64)) -> ContMonad<i64, i64> {
+        let (a, b): (i64, i64) = *p;
+        ContMonad { run_cont: Rc::new(move |k: Rc<dyn Fn(i64) -> i64>| k(a).wrapping_add(k(b))) }
+    }
 
     #[test]
     fn test_monad() {
@@ -210,4 +250,15 @@ mod tests {
         let partial: ContMonad<i32, i32> = do_calc0(cont_unit(10));
         assert_eq!(cont_eval(partial), 112);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_laws() {
+        check_functor_laws(&Config::default(), gen_cont, cont_fmap).unwrap();
+        check_monad_laws(
+            &Config::default(),
+            gen_cont,
+            cont_unit,
+            |m, k| cont_bind(m, ContKleisli { kleisli: k })
+        ).unwrap();
+    }
+}
@@ -0,0 +1,347 @@
+use core::any::Any;
+#[cfg(any(test, feature = "testing"))]
+use core::fmt::Debug;
+use core::marker::PhantomData;
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+#[cfg(any(test, feature = "testing"))]
+use super::observe::ObserveEq;
+
+
+// Recursion in constant native stack.
+// `cont_eval` still runs a `ContMonad` by calling continuations inside
+// continuations, so a deep enough recursion overflows the stack all the same.
+// a `Cps` computation is data instead: a value, a computation put off until
+// it is needed, or a computation followed by a continuation. `run_cps` keeps
+// the continuations waiting for a result on a stack of its own, on the heap,
+// and runs the computation with a loop, so a recursion a million calls deep
+// only grows that stack.
+type Value = Box<dyn Any>;
+
+type Frame = Rc<dyn Fn(Value) -> Next>;
+
+#[derive(Clone)]
+enum Node {
+    Done(Rc<dyn Fn() -> Value>),
+    Defer(Rc<dyn Fn() -> Node>),
+    Bind(Rc<Node>, Frame)
+}
+
+// what a continuation does with a result: give the next one right away
+// (`fmap`), or hand over to another computation (`bind`).
+enum Next {
+    Value(Value),
+    Node(Node)
+}
+
+pub struct Cps<Ta> {
+    node: Node,
+    result: PhantomData<fn() -> Ta>
+}
+
+impl<Ta> Clone for Cps<Ta> {
+    fn clone(&self) -> Self { Cps { node: self.node.clone(), result: PhantomData } }
+}
+
+fn take<Ta: 'static>(x: Value) -> Ta {
+    *x.downcast::<Ta>().unwrap_or_else(|_| panic!("cps: a continuation was given the wrong type"))
+}
+
+
+// a computation is observed by its result.
+#[cfg(any(test, feature = "testing"))]
+impl<Ta: PartialEq + Debug + 'static> ObserveEq for Cps<Ta> {
+    type Input = ();
+    type Output = Ta;
+    fn observe(&self, _: &()) -> Self::Output { run_cps(self.clone()) }
+}
+
+
+pub fn cps_unit<Ta: Clone + 'static>(a: Ta) -> Cps<Ta> {
+    Cps { node: Node::Done(Rc::new(move || -> Value { Box::new(a.clone()) })), result: PhantomData }
+}
+
+// `ma`, not built until it is run.
+pub fn defer<Ta: 'static>(ma: impl Fn() -> Cps<Ta> + 'static) -> Cps<Ta> {
+    Cps { node: Node::Defer(Rc::new(move || -> Node { ma().node })), result: PhantomData }
+}
+
+// a recursive call: `f(a)`, put off until it is run. a recursive function
+// returning a `Cps` makes its calls through `call`, so that building the
+// computation does not recurse either.
+pub fn call<Ta: Clone + 'static, Tr: 'static>(f: fn(Ta) -> Cps<Tr>, a: Ta) -> Cps<Tr> {
+    defer(move || -> Cps<Tr> { f(a.clone()) })
+}
+
+
+// functor
+pub fn cps_fmap<Ta: 'static, Tb: 'static>(
+    f_ab: Rc<dyn Fn(Ta) -> Tb>,
+    ma: Cps<Ta>
+) -> Cps<Tb> {
+    let frame: Frame = Rc::new(move |x: Value| -> Next { Next::Value(Box::new(f_ab(take::<Ta>(x)))) });
+    Cps { node: Node::Bind(Rc::new(ma.node), frame), result: PhantomData }
+}
+
+
+// applicative
+pub fn cps_apply<Ta: 'static, Tb: 'static>(
+    mf: Cps<Rc<dyn Fn(Ta) -> Tb>>,
+    ma: Cps<Ta>
+) -> Cps<Tb> {
+    cps_bind(mf, CpsKleisli { kleisli: Rc::new(move |f_ab: Rc<dyn Fn(Ta) -> Tb>| -> Cps<Tb> { cps_fmap(f_ab, ma.clone()) }) })
+}
+
+
+// monad
+pub struct CpsKleisli<Ta, Tb> {
+    pub kleisli: Rc<dyn Fn(Ta) -> Cps<Tb>>
+}
+
+impl<Ta, Tb> Clone for CpsKleisli<Ta, Tb> {
+    fn clone(&self) -> Self { CpsKleisli { kleisli: self.kleisli.clone() } }
+}
+
+pub fn cps_bind<Ta: 'static, Tb: 'static>(
+    ma: Cps<Ta>,
+    k_ab: CpsKleisli<Ta, Tb>
+) -> Cps<Tb> {
+    let frame: Frame = Rc::new(move |x: Value| -> Next { Next::Node((k_ab.kleisli)(take::<Ta>(x)).node) });
+    Cps { node: Node::Bind(Rc::new(ma.node), frame), result: PhantomData }
+}
+
+
+// the evaluation loop. a bind pushes its continuation and runs the
+// computation before it; a result is handed to the continuations on top of
+// the stack until one of them gives a computation to run.
+pub fn run_cps<Ta: 'static>(ma: Cps<Ta>) -> Ta {
+    let mut stack: Vec<Frame> = Vec::new();
+    let mut node: Node = ma.node;
+    loop {
+        let mut x: Value = match node {
+            Node::Done(a) => a(),
+            Node::Defer(f) => {
+                node = f();
+                continue
+            },
+            Node::Bind(m, k) => {
+                stack.push(k);
+                node = Rc::unwrap_or_clone(m);
+                continue
+            }
+        };
+        node = loop {
+            match stack.pop() {
+                None => return take::<Ta>(x),
+                Some(k) => match k(x) {
+                    Next::Value(y) => x = y,
+                    Next::Node(next) => break next
+                }
+            }
+        };
+    }
+}
+
+// a recursive function written with `call`, as a plain function.
+pub fn recursive<Ta: 'static, Tr: 'static>(f: fn(Ta) -> Cps<Tr>) -> impl Fn(Ta) -> Tr {
+    move |a: Ta| -> Tr { run_cps(f(a)) }
+}
+
+
+// Defunctionalized continuations.
+// for the recursions worth the trouble, the continuations can be plain data:
+// a frame `Tk` says what is left to do with the result of a call. `call`
+// either calls itself on a new argument, saying which frame to resume with
+// its result, or returns; `resume` does what a frame says with a result.
+// frames wait on a `Vec`, and nothing is boxed.
+pub enum Step<Targ, Tk, Tr> {
+    Call(Targ, Tk),
+    Return(Tr)
+}
+
+pub fn run_frames<Targ, Tk, Tr>(
+    arg: Targ,
+    call: impl Fn(Targ) -> Step<Targ, Tk, Tr>,
+    resume: impl Fn(Tk, Tr) -> Step<Targ, Tk, Tr>
+) -> Tr {
+    let mut stack: Vec<Tk> = Vec::new();
+    let mut step: Step<Targ, Tk, Tr> = call(arg);
+    loop {
+        step = match step {
+            Step::Call(a, k) => {
+                stack.push(k);
+                call(a)
+            },
+            Step::Return(r) => match stack.pop() {
+                None => return r,
+                Some(k) => resume(k, r)
+            }
+        };
+    }
+}
+
+
+// do-notation for `Cps`. recursive calls go through `call`.
+#[macro_export]
+macro_rules! cps_do {
+    // trailing comma
+    (@[$($m:tt)*][$($p:tt)*]) => {};
+
+    (@[$($m:tt)*][$($p:tt)*] $v:ident = $e:expr,  $($rest:tt)*) => { (|$v| { $crate::cps_do!(@[$($m)*][$($p)*] $($rest)*) })($e) };
+
+    (@[$($m:tt)*][$($p:tt)*] $v:ident <- $e:expr, $($rest:tt)*) => {
+        $($m)*::cps_bind(
+            $e,
+            $($m)*::CpsKleisli {
+                kleisli: $($p)*::new( move |$v| { $crate::cps_do!(@[$($m)*][$($p)*] $($rest)*) } )
+            }
+        )
+    };
+
+    (@[$($m:tt)*][$($p:tt)*] $e:expr, $($rest:tt)*) => {
+        $($m)*::cps_bind(
+            $e,
+            $($m)*::CpsKleisli {
+                kleisli: $($p)*::new( move |_| { $crate::cps_do!(@[$($m)*][$($p)*] $($rest)*) } )
+            }
+        )
+    };
+
+    (@[$($m:tt)*][$($p:tt)*] $e:expr) => { $e };
+
+    ($($rest:tt)*) => { $crate::cps_do!(@[$crate::monads::cps][$crate::prelude::Rc] $($rest)*) };
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monads::laws::{check_functor_laws, check_monad_laws};
+    use crate::monads::prop::Config;
+
+    const DEPTH: u64 = 1_000_000;
+
+    // 1 + 2 + ... + n, not tail recursive.
+    fn sum_to(n: u64) -> Cps<u64> {
+        if n == 0 { return cps_unit(0) }
+        cps_do!(
+            s <- call(sum_to, n - 1),
+            cps_unit(n + s)
+        )
+    }
+
+    #[test]
+    fn test_deep_recursion() {
+        assert_eq!(run_cps(sum_to(DEPTH)), DEPTH * (DEPTH + 1) / 2);
+        assert_eq!(recursive(sum_to)(10), 55);
+    }
+
+    // a cons list: empty, or a head and the rest.
+    struct List(Option<Rc<(u64, List)>>);
+
+    impl Clone for List {
+        fn clone(&self) -> Self { List(self.0.clone()) }
+    }
+
+    // dropped in a loop, a list this long being too deep for the default drop.
+    impl Drop for List {
+        fn drop(&mut self) {
+            let mut next: Option<Rc<(u64, List)>> = self.0.take();
+            while let Some(cell) = next {
+                next = match Rc::try_unwrap(cell) {
+                    Ok((_, mut rest)) => rest.0.take(),
+                    Err(_) => None
+                };
+            }
+        }
+    }
+
+    fn from_to(from: u64, to: u64) -> List {
+        (from..to).rev().fold(List(None), |rest: List, x: u64| List(Some(Rc::new((x, rest)))))
+    }
+
+    // foldr (\x acc -> x + 2 * acc) 0: the order of the fold matters.
+    fn fold_right(list: List) -> Cps<u64> {
+        match &list.0 {
+            None => cps_unit(0),
+            Some(cell) => {
+                let (x, rest): (u64, List) = (cell.0, cell.1.clone());
+                cps_fmap(Rc::new(move |acc: u64| x.wrapping_add(acc.wrapping_mul(2))), call(fold_right, rest))
+            }
+        }
+    }
+
+    #[test]
+    fn test_list_fold() {
+        assert_eq!(recursive(fold_right)(from_to(1, 4)), 1 + 2 * (2 + 2 * 3));
+        let expected: u64 = (1..DEPTH).rev().fold(0, |acc: u64, x: u64| x.wrapping_add(acc.wrapping_mul(2)));
+        assert_eq!(recursive(fold_right)(from_to(1, DEPTH)), expected);
+    }
+
+    // a comb: a spine of `depth` nodes going left, each with a leaf on its right.
+    #[derive(Clone, Copy)]
+    enum Comb {
+        Spine(u64),
+        Leaf
+    }
+
+    fn size(t: Comb) -> Cps<u64> {
+        match t {
+            Comb::Leaf => cps_unit(1),
+            Comb::Spine(0) => cps_unit(1),
+            Comb::Spine(depth) => cps_do!(
+                l <- call(size, Comb::Spine(depth - 1)),
+                r <- call(size, Comb::Leaf),
+                cps_unit(l + r + 1)
+            )
+        }
+    }
+
+    // the same, with its continuations as data: what is left to do once a
+    // left subtree is sized, and once a right one is.
+    enum SizeFrame {
+        Right(Comb),
+        Add(u64)
+    }
+
+    fn size_frames(t: Comb) -> u64 {
+        run_frames(
+            t,
+            |t: Comb| -> Step<Comb, SizeFrame, u64> {
+                match t {
+                    Comb::Leaf | Comb::Spine(0) => Step::Return(1),
+                    Comb::Spine(depth) => Step::Call(Comb::Spine(depth - 1), SizeFrame::Right(Comb::Leaf))
+                }
+            },
+            |k: SizeFrame, r: u64| -> Step<Comb, SizeFrame, u64> {
+                match k {
+                    SizeFrame::Right(right) => Step::Call(right, SizeFrame::Add(r)),
+                    SizeFrame::Add(l) => Step::Return(l + r + 1)
+                }
+            }
+        )
+    }
+
+    #[test]
+    fn test_tree() {
+        assert_eq!(run_cps(size(Comb::Spine(2))), 5);
+        assert_eq!(run_cps(size(Comb::Spine(DEPTH))), 2 * DEPTH + 1);
+        assert_eq!(size_frames(Comb::Spine(2)), 5);
+        assert_eq!(size_frames(Comb::Spine(DEPTH)), 2 * DEPTH + 1);
+    }
+
+    #[test]
+    fn test_laws() {
+        let gen = |p: &(i64, i64)| -> Cps<i64> { cps_fmap(Rc::new(|x: i64| x.wrapping_mul(3)), cps_unit(p.0 ^ p.1)) };
+        check_functor_laws(&Config::default(), gen, cps_fmap).unwrap();
+        check_monad_laws(
+            &Config::default(),
+            gen,
+            cps_unit,
+            |m, k| cps_bind(m, CpsKleisli { kleisli: k })
+        ).unwrap();
+    }
+}
@@ -0,0 +1,461 @@
+use core::any::Any;
+use core::convert::Infallible;
+use core::marker::PhantomData;
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+use super::cont::{ContMonad, cont_fmap};
+use super::monoid::Monoid;
+
+
+// Algebraic effects.
+// An effect is an operation a computation asks its context to carry out,
+// answered with a `Resume`. a computation performing effects is a
+// `ContMonad` whose answer type is `Eff`: when it performs an operation it
+// stops there and returns the operation together with its continuation. a
+// handler takes that tree apart: it answers the operations it knows, as
+// often as it likes (never, once, or once per choice), and passes on the
+// others to the handlers around it, so effects are interpreted in layers.
+// operations are matched by their type: a `State<i32>` is not handled by a
+// handler for `State<i64>`, and reaches `run` as an unhandled effect.
+pub trait Effect: Clone + 'static {
+    type Resume: 'static;
+}
+
+
+// the rest of a computation, waiting for the answer to an operation.
+pub type Resume<Ta, Tr> = Rc<dyn Fn(Ta) -> Eff<Tr>>;
+
+// the same, for a handler that also takes its next parameter `Ts`.
+pub type ResumeWith<Ts, Ta, Tr> = Rc<dyn Fn(Ts, Ta) -> Eff<Tr>>;
+
+pub enum Eff<Tr> {
+    Pure(Tr),
+    // the operation and its continuation, both with their types erased so
+    // that any handler can look at them. the answer is a `Box<E::Resume>`.
+    Perform(Box<dyn Any>, Resume<Box<dyn Any>, Tr>)
+}
+
+// a computation performing effects, before any are handled.
+pub type Effectful<Tr, Ta> = ContMonad<Eff<Tr>, Ta>;
+
+
+impl<Tr: 'static> Eff<Tr> {
+    // (>>=) for the tree itself, for handlers that combine several resumptions.
+    pub fn and_then<Tb: 'static>(self, k: impl Fn(Tr) -> Eff<Tb> + 'static) -> Eff<Tb> {
+        eff_and_then(self, Rc::new(k))
+    }
+
+    // the result of a computation whose effects have all been handled.
+    pub fn run(self) -> Tr {
+        match self {
+            Eff::Pure(a) => a,
+            Eff::Perform(_, _) => panic!("eff: unhandled effect")
+        }
+    }
+}
+
+fn eff_and_then<Tr: 'static, Tb: 'static>(ma: Eff<Tr>, k: Resume<Tr, Tb>) -> Eff<Tb> {
+    match ma {
+        Eff::Pure(a) => k(a),
+        Eff::Perform(op, k0) => Eff::Perform(op, Rc::new(move |ans: Box<dyn Any>| -> Eff<Tb> {
+            eff_and_then(k0(ans), k.clone())
+        }))
+    }
+}
+
+
+// asks the context to carry out `op`, continuing with its answer.
+pub fn perform<E: Effect, Tr: 'static>(op: E) -> Effectful<Tr, E::Resume> {
+    ContMonad { run_cont: Rc::new(move |k: Resume<E::Resume, Tr>| -> Eff<Tr> {
+        Eff::Perform(Box::new(op.clone()), Rc::new(move |ans: Box<dyn Any>| -> Eff<Tr> {
+            k(*ans.downcast::<E::Resume>().expect("eff: wrong type of answer"))
+        }))
+    }) }
+}
+
+
+// starts the computation, leaving its effects to the handlers.
+pub fn run_eff<Tr: 'static>(ma: Effectful<Tr, Tr>) -> Eff<Tr> {
+    (ma.run_cont)(Rc::new(Eff::Pure))
+}
+
+
+// a handler for the effect `E` that threads a parameter `Ts` through the
+// computation. `ret` turns the final result into the handler's, and `op`
+// answers each operation: it is given the parameter and a resumption that
+// takes the next parameter along with the answer. a resumed computation is
+// handled again, so later operations reach the same handler.
+pub fn handle_with<E: Effect, Ts: Clone + 'static, Tr: 'static, Tb: 'static>(
+    s: Ts,
+    ret: impl Fn(Ts, Tr) -> Eff<Tb> + 'static,
+    op: impl Fn(Ts, E, ResumeWith<Ts, E::Resume, Tb>) -> Eff<Tb> + 'static,
+    ma: Eff<Tr>
+) -> Eff<Tb> {
+    handle_rc(s, Rc::new(ret), Rc::new(op), ma)
+}
+
+fn handle_rc<E, Ts, Tr, Tb, R, O>(s: Ts, ret: Rc<R>, op: Rc<O>, ma: Eff<Tr>) -> Eff<Tb>
+where
+    E: Effect,
+    Ts: Clone + 'static,
+    Tr: 'static,
+    Tb: 'static,
+    R: Fn(Ts, Tr) -> Eff<Tb> + 'static,
+    O: Fn(Ts, E, ResumeWith<Ts, E::Resume, Tb>) -> Eff<Tb> + 'static
+{
+    match ma {
+        Eff::Pure(a) => ret(s, a),
+        Eff::Perform(any_op, k) => match any_op.downcast::<E>() {
+            Ok(e) => {
+                let (ret_k, op_k): (Rc<R>, Rc<O>) = (ret.clone(), op.clone());
+                let resume = Rc::new(move |s: Ts, r: E::Resume| -> Eff<Tb> {
+                    handle_rc(s, ret_k.clone(), op_k.clone(), k(Box::new(r)))
+                });
+                op(s, *e, resume)
+            },
+            // not ours: passed on, to be handled by us again once answered.
+            Err(other) => Eff::Perform(other, Rc::new(move |ans: Box<dyn Any>| -> Eff<Tb> {
+                handle_rc(s.clone(), ret.clone(), op.clone(), k(ans))
+            }))
+        }
+    }
+}
+
+
+// a handler for the effect `E` with nothing to thread through.
+pub fn handle<E: Effect, Tr: 'static, Tb: 'static>(
+    ret: impl Fn(Tr) -> Eff<Tb> + 'static,
+    op: impl Fn(E, Resume<E::Resume, Tb>) -> Eff<Tb> + 'static,
+    ma: Eff<Tr>
+) -> Eff<Tb> {
+    handle_with(
+        (),
+        move |_: (), a: Tr| -> Eff<Tb> { ret(a) },
+        move |_: (), e: E, resume: ResumeWith<(), E::Resume, Tb>| -> Eff<Tb> {
+            op(e, Rc::new(move |r: E::Resume| -> Eff<Tb> { resume((), r) }))
+        },
+        ma
+    )
+}
+
+
+// the built-in effects, to compare with the dedicated monads.
+
+// state: both operations answer with the state as it was.
+#[derive(Clone, Debug, PartialEq)]
+pub enum State<Ts> {
+    Get,
+    Put(Ts)
+}
+
+impl<Ts: Clone + 'static> Effect for State<Ts> {
+    type Resume = Ts;
+}
+
+pub fn get_state<Ts: Clone + 'static, Tr: 'static>() -> Effectful<Tr, Ts> {
+    perform(State::Get)
+}
+
+pub fn put_state<Ts: Clone + 'static, Tr: 'static>(s: Ts) -> Effectful<Tr, ()> {
+    cont_fmap(Rc::new(|_: Ts| ()), perform(State::Put(s)))
+}
+
+pub fn handle_state<Ts: Clone + 'static, Tr: 'static>(s: Ts, ma: Eff<Tr>) -> Eff<(Tr, Ts)> {
+    handle_with(
+        s,
+        |s: Ts, a: Tr| -> Eff<(Tr, Ts)> { Eff::Pure((a, s)) },
+        |s: Ts, e: State<Ts>, resume: ResumeWith<Ts, Ts, (Tr, Ts)>| -> Eff<(Tr, Ts)> {
+            match e {
+                State::Get => resume(s.clone(), s),
+                State::Put(next) => resume(next, s)
+            }
+        },
+        ma
+    )
+}
+
+
+// reader
+pub struct Ask<Tcfg>(PhantomData<Tcfg>);
+
+impl<Tcfg> Clone for Ask<Tcfg> {
+    fn clone(&self) -> Self { Ask(PhantomData) }
+}
+
+impl<Tcfg: 'static> Effect for Ask<Tcfg> {
+    type Resume = Tcfg;
+}
+
+pub fn ask_env<Tcfg: 'static, Tr: 'static>() -> Effectful<Tr, Tcfg> {
+    perform(Ask(PhantomData))
+}
+
+pub fn handle_reader<Tcfg: Clone + 'static, Tr: 'static>(cfg: Tcfg, ma: Eff<Tr>) -> Eff<Tr> {
+    handle(
+        Eff::Pure,
+        move |_: Ask<Tcfg>, resume: Resume<Tcfg, Tr>| -> Eff<Tr> { resume(cfg.clone()) },
+        ma
+    )
+}
+
+
+// writer
+#[derive(Clone, Debug, PartialEq)]
+pub struct Tell<Tlog>(pub Tlog);
+
+impl<Tlog: Clone + 'static> Effect for Tell<Tlog> {
+    type Resume = ();
+}
+
+pub fn tell<Tlog: Clone + 'static, Tr: 'static>(w: Tlog) -> Effectful<Tr, ()> {
+    perform(Tell(w))
+}
+
+pub fn handle_writer<Tlog: Monoid<T = Tlog> + Clone + 'static, Tr: 'static>(ma: Eff<Tr>) -> Eff<(Tr, Tlog)> {
+    handle_with(
+        <Tlog as Monoid>::mempty(),
+        |log: Tlog, a: Tr| -> Eff<(Tr, Tlog)> { Eff::Pure((a, log)) },
+        |mut log: Tlog, Tell(w): Tell<Tlog>, resume: ResumeWith<Tlog, (), (Tr, Tlog)>| -> Eff<(Tr, Tlog)> {
+            log.mappend_assign(w);
+            resume(log, ())
+        },
+        ma
+    )
+}
+
+
+// exceptions: never answered, so whatever follows `raise` is dropped.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Raise<E>(pub E);
+
+impl<E: Clone + 'static> Effect for Raise<E> {
+    type Resume = Infallible;
+}
+
+pub fn raise<E: Clone + 'static, Tr: 'static, Ta: 'static>(e: E) -> Effectful<Tr, Ta> {
+    cont_fmap(Rc::new(|never: Infallible| -> Ta { match never {} }), perform(Raise(e)))
+}
+
+pub fn handle_exception<E: Clone + 'static, Tr: 'static>(ma: Eff<Tr>) -> Eff<Result<Tr, E>> {
+    handle(
+        |a: Tr| -> Eff<Result<Tr, E>> { Eff::Pure(Ok(a)) },
+        |Raise(e): Raise<E>, _: Resume<Infallible, Result<Tr, E>>| -> Eff<Result<Tr, E>> { Eff::Pure(Err(e)) },
+        ma
+    )
+}
+
+
+// nondeterminism: `choose` is answered with both `true` and `false`, one
+// after the other, and `fail` with neither.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Choose;
+
+impl Effect for Choose {
+    type Resume = bool;
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Fail;
+
+impl Effect for Fail {
+    type Resume = Infallible;
+}
+
+pub fn choose<Tr: 'static>() -> Effectful<Tr, bool> {
+    perform(Choose)
+}
+
+pub fn fail<Tr: 'static, Ta: 'static>() -> Effectful<Tr, Ta> {
+    cont_fmap(Rc::new(|never: Infallible| -> Ta { match never {} }), perform(Fail))
+}
+
+// every result, in the order the choices were made.
+pub fn handle_choice<Tr: Clone + 'static>(ma: Eff<Tr>) -> Eff<Vec<Tr>> {
+    let failed: Eff<Vec<Tr>> = handle(
+        |a: Tr| -> Eff<Vec<Tr>> { Eff::Pure(alloc::vec![a]) },
+        |_: Fail, _: Resume<Infallible, Vec<Tr>>| -> Eff<Vec<Tr>> { Eff::Pure(Vec::new()) },
+        ma
+    );
+    handle(
+        Eff::Pure,
+        |_: Choose, resume: Resume<bool, Vec<Tr>>| -> Eff<Vec<Tr>> {
+            let resume_false: Resume<bool, Vec<Tr>> = resume.clone();
+            resume(true).and_then(move |xs: Vec<Tr>| -> Eff<Vec<Tr>> {
+                resume_false(false).and_then(move |ys: Vec<Tr>| -> Eff<Vec<Tr>> {
+                    let mut xs: Vec<Tr> = xs.clone();
+                    xs.extend(ys);
+                    Eff::Pure(xs)
+                })
+            })
+        },
+        failed
+    )
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+    use crate::cont_do;
+    use crate::monads::cont::cont_unit;
+
+    #[test]
+    #[cfg(feature = "state")]
+    fn test_state() {
+        use crate::monads::state::{StateMonad, get, put, state_unit};
+        use crate::state_do;
+
+        let counter: Effectful<i64, i64> = cont_do!(
+            x <- get_state::<i64, i64>(),
+            put_state(x + 1),
+            y <- get_state::<i64, i64>(),
+            put_state(y * 10),
+            cont_unit(x + y)
+        );
+        let s0: i64 = 3;
+        assert_eq!(handle_state(s0, run_eff(counter)).run(), (7, 40));
+
+        let dedicated: StateMonad<i64, i64> = state_do!(
+            x <- get(),
+            put(x + 1),
+            y <- get(),
+            put(y * 10),
+            state_unit(x + y)
+        );
+        assert_eq!((dedicated.run_state)(3), (7, 40));
+    }
+
+    #[test]
+    #[cfg(feature = "reader")]
+    fn test_reader() {
+        use crate::monads::reader::{ReaderMonad, asks, reader_unit};
+        use crate::reader_do;
+
+        let m: Effectful<usize, usize> = cont_do!(
+            name <- ask_env::<String, usize>(),
+            cont_unit(name.len() * 2)
+        );
+        assert_eq!(handle_reader("effects".to_string(), run_eff(m)).run(), 14);
+
+        let dedicated: ReaderMonad<String, usize> = reader_do!(
+            n <- asks(|name: &String| name.len()),
+            reader_unit(n * 2)
+        );
+        assert_eq!((dedicated.run_reader)(&"effects".to_string()), 14);
+    }
+
+    #[test]
+    #[cfg(feature = "writer")]
+    fn test_writer() {
+        use crate::monads::writer::{WriterMonad, log, writer_unit};
+        use crate::writer_do;
+
+        let m: Effectful<i64, i64> = cont_do!(
+            tell(vec!["start"]),
+            x <- cont_unit(6),
+            tell(vec!["multiply"]),
+            cont_unit(x * 7)
+        );
+        assert_eq!(handle_writer(run_eff(m)).run(), (42, vec!["start", "multiply"]));
+
+        let dedicated: WriterMonad<i64, Vec<&str>> = writer_do!(
+            log(vec!["start"]),
+            x <- writer_unit(6),
+            log(vec!["multiply"]),
+            writer_unit(x * 7)
+        );
+        assert_eq!(dedicated.run_writer(), (42, vec!["start", "multiply"]));
+    }
+
+    fn safe_div(a: i64, b: i64) -> Effectful<i64, i64> {
+        if b == 0 { return raise("division by zero".to_string()) }
+        cont_unit(a / b)
+    }
+
+    #[test]
+    fn test_exception() {
+        let ok: Effectful<i64, i64> = cont_do!(x <- safe_div(10, 2), cont_unit(x + 1));
+        assert_eq!(handle_exception::<String, i64>(run_eff(ok)).run(), Ok(6));
+
+        let err: Effectful<i64, i64> = cont_do!(
+            x <- safe_div(10, 0),
+            y <- safe_div(x, 1),
+            cont_unit(y + 1)
+        );
+        assert_eq!(handle_exception::<String, i64>(run_eff(err)).run(), Err("division by zero".to_string()));
+    }
+
+    // the order of the handlers decides whether a raise rolls the state back.
+    #[test]
+    fn test_layers() {
+        let m = || -> Effectful<i64, i64> { cont_do!(
+            put_state::<i64, i64>(1),
+            _x <- raise::<&str, i64, i64>("stop"),
+            put_state::<i64, i64>(2),
+            cont_unit(0)
+        ) };
+        let s0: i64 = 0;
+        assert_eq!(handle_exception::<&str, (i64, i64)>(handle_state(s0, run_eff(m()))).run(), Err("stop"));
+        assert_eq!(handle_state(s0, handle_exception::<&str, i64>(run_eff(m()))).run(), (Err("stop"), 1));
+    }
+
+    fn digit() -> Effectful<(i64, i64, i64), i64> {
+        cont_do!(
+            b0 <- choose(),
+            b1 <- choose(),
+            cont_unit(2 * (b0 as i64) + (b1 as i64))
+        )
+    }
+
+    #[test]
+    fn test_choice() {
+        // every choice is resumed twice, and `fail` not at all.
+        let squares: Effectful<(i64, i64, i64), (i64, i64, i64)> = cont_do!(
+            a <- digit(),
+            b <- digit(),
+            if a < b { cont_unit((a, b, a * a + b * b)) } else { fail() }
+        );
+        assert_eq!(
+            handle_choice(run_eff(squares)).run(),
+            vec![(2, 3, 13), (1, 3, 10), (1, 2, 5), (0, 3, 9), (0, 2, 4), (0, 1, 1)]
+        );
+    }
+
+    // a user defined effect: fresh names, each resumption given the next.
+    #[derive(Clone)]
+    struct Fresh;
+
+    impl Effect for Fresh {
+        type Resume = String;
+    }
+
+    #[test]
+    fn test_custom() {
+        let m: Effectful<String, String> = cont_do!(
+            a <- perform(Fresh),
+            b <- perform(Fresh),
+            cont_unit(a.clone() + "," + &b)
+        );
+        let named: Eff<String> = handle_with(
+            0,
+            |_: usize, a: String| -> Eff<String> { Eff::Pure(a) },
+            |n: usize, _: Fresh, resume: ResumeWith<usize, String, String>| -> Eff<String> {
+                resume(n + 1, alloc::format!("x{}", n))
+            },
+            run_eff(m)
+        );
+        assert_eq!(named.run(), "x0,x1");
+    }
+
+    #[test]
+    #[should_panic(expected = "unhandled effect")]
+    fn test_unhandled() {
+        let m: Effectful<i64, i64> = cont_do!(x <- get_state::<i64, i64>(), cont_unit(x));
+        run_eff(m).run();
+    }
+}
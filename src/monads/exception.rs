@@ -0,0 +1,189 @@
+use alloc::rc::Rc;
+
+use super::cont::{ContMonad, ContKleisli, RunCont, cont_bind, cont_unit};
+
+
+// Exceptions with double-barrelled continuations.
+// a computation is given two continuations: the one to carry on with if it
+// succeeds, and the one to jump to if it throws. the second is handed over
+// only once the first has been, so the answer of an `Exc` is itself waiting
+// for the failure continuation:
+//
+// Exc e r a = ContT (( e -> r) -> r) a
+//           = (a -> (e -> r) -> r) -> (e -> r) -> r
+//
+// `throw` jumps to the failure continuation, and `try_catch` runs its body
+// with a failure continuation of its own, putting the outer one back before
+// carrying on, so that what comes after a `try_catch` is not caught by it.
+pub type OnError<Te, Tr> = Rc<dyn Fn(Te) -> Tr>;
+
+pub type Catching<Te, Tr> = Rc<dyn Fn(OnError<Te, Tr>) -> Tr>;
+
+pub type Exc<Te, Tr, Ta> = ContMonad<Catching<Te, Tr>, Ta>;
+
+// the success continuation, and what a `try_catch` does with an exception.
+type Then<Te, Tr, Ta> = Rc<dyn Fn(Ta) -> Catching<Te, Tr>>;
+
+type Handler<Te, Tr, Ta> = Rc<dyn Fn(Te) -> Exc<Te, Tr, Ta>>;
+
+
+// throws `e` to the innermost `try_catch`, skipping the rest of the computation.
+pub fn throw<Te: Clone + 'static, Tr: 'static, Ta: 'static>(e: Te) -> Exc<Te, Tr, Ta> {
+    ContMonad { run_cont: Rc::new(move |_: Then<Te, Tr, Ta>| -> Catching<Te, Tr> {
+        let e: Te = e.clone();
+        Rc::new(move |on_error: OnError<Te, Tr>| -> Tr { on_error(e.clone()) })
+    }) }
+}
+
+// `body`, or `handler` on what it throws. the handler may throw in turn,
+// to the `try_catch` around this one.
+pub fn try_catch<Te: 'static, Tr: 'static, Ta: 'static>(
+    body: Exc<Te, Tr, Ta>,
+    handler: ContKleisli<Catching<Te, Tr>, Te, Ta>
+) -> Exc<Te, Tr, Ta> {
+    let run_body: RunCont<Catching<Te, Tr>, Ta> = body.run_cont;
+    let handle: Handler<Te, Tr, Ta> = handler.kleisli;
+    ContMonad { run_cont: Rc::new(move |k: Then<Te, Tr, Ta>| -> Catching<Te, Tr> {
+        let run_body: RunCont<Catching<Te, Tr>, Ta> = run_body.clone();
+        let handle: Handler<Te, Tr, Ta> = handle.clone();
+        Rc::new(move |outer: OnError<Te, Tr>| -> Tr {
+            // on success, the body's failure continuation is dropped for the outer one.
+            let (k_ok, outer_ok): (Then<Te, Tr, Ta>, OnError<Te, Tr>) = (k.clone(), outer.clone());
+            let succeed: Then<Te, Tr, Ta> = Rc::new(move |a: Ta| -> Catching<Te, Tr> {
+                let (then, outer): (Catching<Te, Tr>, OnError<Te, Tr>) = (k_ok(a), outer_ok.clone());
+                Rc::new(move |_: OnError<Te, Tr>| -> Tr { then(outer.clone()) })
+            });
+            let (k_err, handle): (Then<Te, Tr, Ta>, Handler<Te, Tr, Ta>) = (k.clone(), handle.clone());
+            let outer: OnError<Te, Tr> = outer.clone();
+            let catch: OnError<Te, Tr> = Rc::new(move |e: Te| -> Tr {
+                (handle(e).run_cont)(k_err.clone())(outer.clone())
+            });
+            run_body(succeed)(catch)
+        })
+    }) }
+}
+
+// `body`, then `cleanup`, whether `body` succeeds or throws. what `body`
+// throws is thrown again once `cleanup` is done.
+pub fn finally<Te: Clone + 'static, Tr: 'static, Ta: Clone + 'static>(
+    body: Exc<Te, Tr, Ta>,
+    cleanup: Exc<Te, Tr, ()>
+) -> Exc<Te, Tr, Ta> {
+    let on_error: Exc<Te, Tr, ()> = cleanup.clone();
+    let caught: Exc<Te, Tr, Ta> = try_catch(body, ContKleisli { kleisli: Rc::new(move |e: Te| -> Exc<Te, Tr, Ta> {
+        cont_bind(on_error.clone(), ContKleisli { kleisli: Rc::new(move |_: ()| -> Exc<Te, Tr, Ta> { throw(e.clone()) }) })
+    }) });
+    cont_bind(caught, ContKleisli { kleisli: Rc::new(move |a: Ta| -> Exc<Te, Tr, Ta> {
+        cont_bind(cleanup.clone(), ContKleisli { kleisli: Rc::new(move |_: ()| -> Exc<Te, Tr, Ta> { cont_unit(a.clone()) }) })
+    }) })
+}
+
+
+// runs a computation, with what it throws as the error.
+pub fn run_exc<Te: 'static, Ta: Clone + 'static>(ma: Exc<Te, Result<Ta, Te>, Ta>) -> Result<Ta, Te> {
+    let done: Then<Te, Result<Ta, Te>, Ta> = Rc::new(|a: Ta| -> Catching<Te, Result<Ta, Te>> {
+        Rc::new(move |_: OnError<Te, Result<Ta, Te>>| -> Result<Ta, Te> { Ok(a.clone()) })
+    });
+    (ma.run_cont)(done)(Rc::new(Err))
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::RefCell;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use crate::cont_do;
+
+    #[derive(Debug, Clone, PartialEq)]
+    enum Error {
+        DivByZero,
+        Negative(i64)
+    }
+
+    type Calc<Ta> = Exc<Error, Result<i64, Error>, Ta>;
+
+    fn div(a: i64, b: i64) -> Calc<i64> {
+        if b == 0 { throw(Error::DivByZero) } else { cont_unit(a / b) }
+    }
+
+    fn positive(a: i64) -> Calc<i64> {
+        if a < 0 { throw(Error::Negative(a)) } else { cont_unit(a) }
+    }
+
+    fn recover(value: i64) -> ContKleisli<Catching<Error, Result<i64, Error>>, Error, i64> {
+        ContKleisli { kleisli: Rc::new(move |_: Error| -> Calc<i64> { cont_unit(value) }) }
+    }
+
+    #[test]
+    fn test_throw() {
+        let ok: Calc<i64> = cont_do!(x <- div(10, 2), positive(x));
+        assert_eq!(run_exc(ok), Ok(5));
+
+        // nothing after the throw runs.
+        let thrown: Calc<i64> = cont_do!(x <- div(10, 0), y <- positive(-1), cont_unit(x + y));
+        assert_eq!(run_exc(thrown), Err(Error::DivByZero));
+    }
+
+    #[test]
+    fn test_try_catch() {
+        let caught: Calc<i64> = cont_do!(
+            x <- try_catch(div(1, 0), recover(100)),
+            y <- positive(x),
+            cont_unit(y + 1)
+        );
+        assert_eq!(run_exc(caught), Ok(101));
+
+        // what comes after a try_catch is not caught by it.
+        let after: Calc<i64> = cont_do!(
+            x <- try_catch(div(4, 2), recover(100)),
+            positive(-x)
+        );
+        assert_eq!(run_exc(after), Err(Error::Negative(-2)));
+
+        // a handler throwing goes to the try_catch around it.
+        let rethrown: Calc<i64> = try_catch(
+            try_catch(div(1, 0), ContKleisli { kleisli: Rc::new(|_: Error| positive(-7)) }),
+            ContKleisli { kleisli: Rc::new(|e: Error| cont_unit(if e == Error::Negative(-7) { 7 } else { 0 })) }
+        );
+        assert_eq!(run_exc(rethrown), Ok(7));
+    }
+
+    // records `event` when it runs.
+    fn note(log: &Rc<RefCell<Vec<&'static str>>>, event: &'static str) -> Calc<()> {
+        let log: Rc<RefCell<Vec<&'static str>>> = log.clone();
+        ContMonad { run_cont: Rc::new(move |k: Then<Error, Result<i64, Error>, ()>| {
+            log.borrow_mut().push(event);
+            k(())
+        }) }
+    }
+
+    #[test]
+    fn test_finally() {
+        let log: Rc<RefCell<Vec<&'static str>>> = Rc::new(RefCell::new(Vec::new()));
+        let ok: Calc<i64> = finally(div(9, 3), note(&log, "cleanup"));
+        assert_eq!(run_exc(ok), Ok(3));
+        assert_eq!(*log.borrow(), vec!["cleanup"]);
+
+        log.borrow_mut().clear();
+        let after: Calc<()> = note(&log, "after");
+        let thrown: Calc<i64> = cont_do!(
+            x <- finally(div(9, 0), note(&log, "cleanup")),
+            _u <- after.clone(),
+            cont_unit(x)
+        );
+        assert_eq!(run_exc(thrown), Err(Error::DivByZero));
+        assert_eq!(*log.borrow(), vec!["cleanup"]);
+
+        // the cleanup runs before the handler around it.
+        log.borrow_mut().clear();
+        let handled: Calc<i64> = try_catch(
+            finally(positive(-3), note(&log, "cleanup")),
+            ContKleisli { kleisli: Rc::new(|_: Error| cont_unit(0)) }
+        );
+        assert_eq!(run_exc(handled), Ok(0));
+        assert_eq!(*log.borrow(), vec!["cleanup"]);
+    }
+}
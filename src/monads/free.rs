@@ -0,0 +1,333 @@
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+
+
+// A functor as a type-level function `F<_>` with its map, standing in for
+// the `f` of Haskell's `Free f a`. the map is handed an `Rc` since functors
+// holding continuations (`Get(key, Rc<dyn Fn(Value) -> X>)`) keep it.
+pub trait Functor {
+    type F<Ta>;
+
+    fn fmap<Ta: 'static, Tb: 'static>(f_ab: Rc<dyn Fn(Ta) -> Tb>, fa: Self::F<Ta>) -> Self::F<Tb>;
+}
+
+
+// functors whose values can be copied whenever what they hold can.
+// programs over them are `Clone`, so they can be run more than once and
+// interpreted into the `Rc` based monads, whose functions may run many times.
+pub trait CloneFunctor: Functor {
+    fn clone_f<Ta: Clone>(fa: &Self::F<Ta>) -> Self::F<Ta>;
+}
+
+
+// Free f a = Pure a | Free (f (Free f a))
+// a program over the instruction set `F` returning an `a`: either finished,
+// or suspended on an instruction whose continuation is the rest of the program.
+pub enum Free<F: Functor, Ta> {
+    Pure(Ta),
+    Suspend(Box<F::F<Free<F, Ta>>>)
+}
+
+impl<F: CloneFunctor, Ta: Clone> Clone for Free<F, Ta> {
+    fn clone(&self) -> Self {
+        match self {
+            Free::Pure(a) => Free::Pure(a.clone()),
+            Free::Suspend(fx) => Free::Suspend(Box::new(F::clone_f(fx)))
+        }
+    }
+}
+
+
+pub fn free_unit<F: Functor, Ta>(a: Ta) -> Free<F, Ta> {
+    Free::Pure(a)
+}
+
+
+// functor
+pub fn free_fmap<F: Functor + 'static, Ta: 'static, Tb: 'static>(
+    f_ab: Rc<dyn Fn(Ta) -> Tb>,
+    ma: Free<F, Ta>
+) -> Free<F, Tb> {
+    match ma {
+        Free::Pure(a) => Free::Pure(f_ab(a)),
+        Free::Suspend(fx) => Free::Suspend(Box::new(F::fmap(
+            Rc::new(move |next: Free<F, Ta>| -> Free<F, Tb> { free_fmap(f_ab.clone(), next) }),
+            *fx
+        )))
+    }
+}
+
+
+// applicative
+pub fn free_apply<F: CloneFunctor + 'static, Ta: Clone + 'static, Tb: 'static>(
+    mf: Free<F, Rc<dyn Fn(Ta) -> Tb>>,
+    ma: Free<F, Ta>
+) -> Free<F, Tb> {
+    free_bind(mf, FreeKleisli { kleisli: Rc::new(move |f_ab: Rc<dyn Fn(Ta) -> Tb>| -> Free<F, Tb> { free_fmap(f_ab, ma.clone()) }) })
+}
+
+
+// monad
+pub struct FreeKleisli<F: Functor, Ta, Tb> {
+    pub kleisli: Rc<dyn Fn(Ta) -> Free<F, Tb>>
+}
+
+impl<F: Functor, Ta, Tb> Clone for FreeKleisli<F, Ta, Tb> {
+    fn clone(&self) -> Self { FreeKleisli { kleisli: self.kleisli.clone() } }
+}
+
+pub fn free_bind<F: Functor + 'static, Ta: 'static, Tb: 'static>(
+    ma: Free<F, Ta>,
+    k_ab: FreeKleisli<F, Ta, Tb>
+) -> Free<F, Tb> {
+    match ma {
+        Free::Pure(a) => (k_ab.kleisli)(a),
+        Free::Suspend(fx) => Free::Suspend(Box::new(F::fmap(
+            Rc::new(move |next: Free<F, Ta>| -> Free<F, Tb> { free_bind(next, k_ab.clone()) }),
+            *fx
+        )))
+    }
+}
+
+
+// liftF :: f a -> Free f a
+// a program made of the single instruction `fa`.
+pub fn lift_f<F: Functor + 'static, Ta: 'static>(fa: F::F<Ta>) -> Free<F, Ta> {
+    Free::Suspend(Box::new(F::fmap(Rc::new(Free::Pure), fa)))
+}
+
+
+// what `fold_free` binds each instruction to: the rest of the program, interpreted.
+type Rest<F, Ta, Ma> = Rc<dyn Fn(Free<F, Ta>) -> Ma>;
+
+// foldFree :: Monad m => (forall x. f x -> m x) -> Free f a -> m a
+// interprets a program into another monad, instruction by instruction.
+// without higher kinded types the target monad is handed over as functions:
+// `interpret` runs one instruction, producing the rest of the program in the
+// target monad (usually a generic `fn` instantiated at `Free<F, Ta>`), and
+// `unit`/`bind` are the target's own.
+pub fn fold_free<F: Functor + 'static, Ta: 'static, Mx: 'static, Ma: 'static>(
+    interpret: fn(F::F<Free<F, Ta>>) -> Mx,
+    unit: fn(Ta) -> Ma,
+    bind: fn(Mx, Rest<F, Ta, Ma>) -> Ma,
+    ma: Free<F, Ta>
+) -> Ma {
+    match ma {
+        Free::Pure(a) => unit(a),
+        Free::Suspend(fx) => bind(
+            interpret(*fx),
+            Rc::new(move |next: Free<F, Ta>| -> Ma { fold_free(interpret, unit, bind, next) })
+        )
+    }
+}
+
+
+// do-notation. internally every rule carries the module to take the monad
+// from and the pointer its kleisli arrows are stored in.
+#[macro_export]
+macro_rules! free_do {
+    // trailing comma
+    (@[$($m:tt)*][$($p:tt)*]) => {};
+
+    (@[$($m:tt)*][$($p:tt)*] $v:ident = $e:expr,  $($rest:tt)*) => { (|$v| { $crate::free_do!(@[$($m)*][$($p)*] $($rest)*) })($e) };
+
+    (@[$($m:tt)*][$($p:tt)*] $v:ident <- $e:expr, $($rest:tt)*) => {
+        $($m)*::free_bind(
+            $e,
+            $($m)*::FreeKleisli {
+                kleisli: $($p)*::new( move |$v| { $crate::free_do!(@[$($m)*][$($p)*] $($rest)*) } )
+            }
+        )
+    };
+
+    (@[$($m:tt)*][$($p:tt)*] $e:expr, $($rest:tt)*) => {
+        $($m)*::free_bind(
+            $e,
+            $($m)*::FreeKleisli {
+                kleisli: $($p)*::new( move |_| { $crate::free_do!(@[$($m)*][$($p)*] $($rest)*) } )
+            }
+        )
+    };
+
+    (@[$($m:tt)*][$($p:tt)*] $e:expr) => { $e };
+
+    ($($rest:tt)*) => { $crate::free_do!(@[$crate::monads::free][$crate::prelude::Rc] $($rest)*) };
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    #[cfg(any(feature = "state", feature = "reader"))]
+    use alloc::collections::BTreeMap;
+    use alloc::string::{String, ToString};
+    #[cfg(feature = "state")]
+    use crate::monads::laws::{check_functor_laws, check_monad_laws};
+    #[cfg(feature = "state")]
+    use crate::monads::observe::ObserveEq;
+    #[cfg(feature = "state")]
+    use crate::monads::prop::Config;
+    #[cfg(feature = "state")]
+    use crate::monads::state::{StateMonad, StateKleisli, state_unit, state_bind};
+
+    // a key-value store, as an instruction set.
+    #[derive(Clone)]
+    enum KvF<X> {
+        Put(String, String, X),
+        Get(String, Rc<dyn Fn(Option<String>) -> X>),
+        Delete(String, X)
+    }
+
+    enum Kv {}
+
+    impl Functor for Kv {
+        type F<X> = KvF<X>;
+        fn fmap<Ta: 'static, Tb: 'static>(f_ab: Rc<dyn Fn(Ta) -> Tb>, fa: KvF<Ta>) -> KvF<Tb> {
+            match fa {
+                KvF::Put(key, value, next) => KvF::Put(key, value, f_ab(next)),
+                KvF::Get(key, k) => KvF::Get(key, Rc::new(move |value: Option<String>| -> Tb { f_ab(k(value)) })),
+                KvF::Delete(key, next) => KvF::Delete(key, f_ab(next))
+            }
+        }
+    }
+
+    impl CloneFunctor for Kv {
+        fn clone_f<Ta: Clone>(fa: &KvF<Ta>) -> KvF<Ta> { fa.clone() }
+    }
+
+    #[cfg(any(feature = "state", feature = "reader"))]
+    type Store = BTreeMap<String, String>;
+
+    fn put(key: &str, value: &str) -> Free<Kv, ()> { lift_f(KvF::Put(key.to_string(), value.to_string(), ())) }
+    fn get(key: &str) -> Free<Kv, Option<String>> { lift_f(KvF::Get(key.to_string(), Rc::new(|value| value))) }
+    fn delete(key: &str) -> Free<Kv, ()> { lift_f(KvF::Delete(key.to_string(), ())) }
+
+    // the workflow under test: moves a value to another key, if there is one.
+    fn rename(from: &'static str, to: &'static str) -> Free<Kv, bool> {
+        free_do!(
+            value <- get(from),
+            match value {
+                Some(value) => free_do!(delete(from), put(to, &value), free_unit(true)),
+                None => free_unit(false)
+            }
+        )
+    }
+
+    // counts the instructions instead of running them.
+    fn steps<Ta>(ma: &Free<Kv, Ta>) -> usize {
+        match ma {
+            Free::Pure(_) => 0,
+            Free::Suspend(fx) => match &**fx {
+                KvF::Put(_, _, next) | KvF::Delete(_, next) => 1 + steps(next),
+                KvF::Get(_, _) => 1
+            }
+        }
+    }
+
+    #[test]
+    fn test_structure() {
+        let m: Free<Kv, i64> = free_fmap(Rc::new(|x: i64| x + 1), free_do!(put("a", "1"), free_unit(41)));
+        assert_eq!(steps(&m), 1);
+        assert_eq!(steps(&rename("a", "b")), 1);
+        assert!(matches!(free_bind(m, FreeKleisli { kleisli: Rc::new(free_unit::<Kv, i64>) }), Free::Suspend(_)));
+
+        let m: Free<Kv, i64> = free_apply(free_unit(Rc::new(|x: i64| x * 2) as Rc<dyn Fn(i64) -> i64>), free_unit(21));
+        assert!(matches!(m, Free::Pure(42)));
+    }
+
+    // a pure interpreter, for tests: the store is threaded as the state.
+    #[cfg(feature = "state")]
+    fn into_state<X: Clone + 'static>(cmd: KvF<X>) -> StateMonad<Store, X> {
+        StateMonad { run_state: Rc::new(move |mut store: Store| -> (X, Store) {
+            match &cmd {
+                KvF::Put(key, value, next) => { store.insert(key.clone(), value.clone()); (next.clone(), store) },
+                KvF::Get(key, k) => (k(store.get(key).cloned()), store),
+                KvF::Delete(key, next) => { store.remove(key); (next.clone(), store) }
+            }
+        }) }
+    }
+
+    #[cfg(feature = "state")]
+    fn run<Ta: Copy + 'static>(program: Free<Kv, Ta>, store: Store) -> (Ta, Store) {
+        let m: StateMonad<Store, Ta> = fold_free(
+            into_state::<Free<Kv, Ta>>,
+            state_unit,
+            |m, k| state_bind(m, StateKleisli { kleisli: k }),
+            program
+        );
+        (m.run_state)(store)
+    }
+
+    #[test]
+    #[cfg(feature = "state")]
+    fn test_into_state() {
+        let store: Store = [("a".to_string(), "1".to_string())].into_iter().collect();
+        let renamed: Store = [("b".to_string(), "1".to_string())].into_iter().collect();
+        assert_eq!(run(rename("a", "b"), store.clone()), (true, renamed));
+        assert_eq!(run(rename("c", "b"), store.clone()), (false, store.clone()));
+
+        // the program is a value, and running it does not use it up.
+        let program: Free<Kv, bool> = rename("a", "b");
+        assert_eq!(run(program.clone(), store.clone()), run(program, store));
+    }
+
+    #[test]
+    #[cfg(feature = "reader")]
+    fn test_into_reader() {
+        use core::cell::RefCell;
+        use crate::monads::reader::{ReaderMonad, ReaderKleisli, reader_unit, reader_bind};
+
+        // an effectful interpreter, as in production: the instructions go
+        // to a store the reader is given a handle to.
+        fn into_reader<X: Clone + 'static>(cmd: KvF<X>) -> ReaderMonad<RefCell<Store>, X> {
+            ReaderMonad { run_reader: Rc::new(move |db: &RefCell<Store>| -> X {
+                match &cmd {
+                    KvF::Put(key, value, next) => { db.borrow_mut().insert(key.clone(), value.clone()); next.clone() },
+                    KvF::Get(key, k) => k(db.borrow().get(key).cloned()),
+                    KvF::Delete(key, next) => { db.borrow_mut().remove(key); next.clone() }
+                }
+            }) }
+        }
+
+        let m: ReaderMonad<RefCell<Store>, bool> = fold_free(
+            into_reader::<Free<Kv, bool>>,
+            reader_unit,
+            |m, k| reader_bind(m, ReaderKleisli { kleisli: k }),
+            rename("a", "b")
+        );
+        let db: RefCell<Store> = RefCell::new([("a".to_string(), "1".to_string())].into_iter().collect());
+        assert!((m.run_reader)(&db));
+        assert_eq!(db.borrow().get("b"), Some(&"1".to_string()));
+        assert!(!(m.run_reader)(&db));
+    }
+
+    // programs are observed by running them on an arbitrary store.
+    #[cfg(feature = "state")]
+    struct Program(Free<Kv, i64>);
+
+    #[cfg(feature = "state")]
+    impl ObserveEq for Program {
+        type Input = Store;
+        type Output = (i64, Store);
+        fn observe(&self, store: &Store) -> Self::Output { run(self.0.clone(), store.clone()) }
+    }
+
+    #[test]
+    #[cfg(feature = "state")]
+    fn test_laws() {
+        fn gen(p: &(String, String, i64)) -> Program {
+            let (from, to, a): (String, String, i64) = p.clone();
+            Program(free_bind(get(&from), FreeKleisli { kleisli: Rc::new(move |value: Option<String>| {
+                free_fmap(Rc::new(move |_| a), put(&to, &value.unwrap_or_default()))
+            }) }))
+        }
+        check_functor_laws(&Config::default(), gen, |f, m| Program(free_fmap(f, m.0))).unwrap();
+        check_monad_laws(
+            &Config::default(),
+            gen,
+            |a| Program(free_unit(a)),
+            |m, k| Program(free_bind(m.0, FreeKleisli { kleisli: Rc::new(move |a| k(a).0) }))
+        ).unwrap();
+    }
+}
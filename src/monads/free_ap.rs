@@ -0,0 +1,277 @@
+use alloc::rc::Rc;
+use alloc::vec::{IntoIter, Vec};
+
+use super::instr::{Answer, Instr};
+use super::monoid::{Monoid, fold_map};
+#[cfg(feature = "reader")]
+use super::reader::ReaderMonad;
+
+
+// The free applicative.
+// A computation over instructions `I`, each an `Instr` answered with its own
+// `Output`, producing an `a`. since `apply` cannot make an instruction depend
+// on the answer to another, every instruction is known before anything
+// runs: they are kept in a list that can be inspected (`analyze`) and,
+// separately, a function that builds the result out of their answers, in
+// the same order.
+
+
+// builds the result, taking the answers it needs off the front.
+pub type Build<Ta> = Rc<dyn Fn(&mut IntoIter<Answer>) -> Ta>;
+
+
+pub struct FreeAp<I, Ta> {
+    pub instrs: Vec<I>,
+    pub build: Build<Ta>
+}
+
+impl<I: Clone, Ta> Clone for FreeAp<I, Ta> {
+    fn clone(&self) -> Self { FreeAp { instrs: self.instrs.clone(), build: self.build.clone() } }
+}
+
+
+pub fn free_ap_unit<I, Ta: 'static + Clone>(a: Ta) -> FreeAp<I, Ta> {
+    FreeAp { instrs: Vec::new(), build: Rc::new(move |_: &mut IntoIter<Answer>| -> Ta { a.clone() }) }
+}
+
+
+// liftAp :: f a -> Ap f a
+// the computation made of the single instruction `op`, giving its answer.
+pub fn lift_ap<I, Op: Instr + Into<I>>(op: Op) -> FreeAp<I, Op::Output> {
+    FreeAp {
+        instrs: alloc::vec![op.into()],
+        build: Rc::new(|answers: &mut IntoIter<Answer>| -> Op::Output { answers.next().expect("free_ap: missing answer").take() })
+    }
+}
+
+
+// functor
+pub fn free_ap_fmap<I, Ta: 'static, Tb: 'static>(
+    f_ab: Rc<dyn Fn(Ta) -> Tb>,
+    ma: FreeAp<I, Ta>
+) -> FreeAp<I, Tb> {
+    let build: Build<Ta> = ma.build;
+    FreeAp { instrs: ma.instrs, build: Rc::new(move |answers: &mut IntoIter<Answer>| -> Tb { f_ab(build(answers)) }) }
+}
+
+
+// applicative
+// the instructions of `mf` come before those of `ma`, and so do their answers.
+pub fn free_ap_apply<I, Ta: 'static, Tb: 'static>(
+    mf: FreeAp<I, Rc<dyn Fn(Ta) -> Tb>>,
+    ma: FreeAp<I, Ta>
+) -> FreeAp<I, Tb> {
+    let mut instrs: Vec<I> = mf.instrs;
+    instrs.extend(ma.instrs);
+    let build_f: Build<Rc<dyn Fn(Ta) -> Tb>> = mf.build;
+    let build_a: Build<Ta> = ma.build;
+    FreeAp { instrs, build: Rc::new(move |answers: &mut IntoIter<Answer>| -> Tb {
+        let f_ab: Rc<dyn Fn(Ta) -> Tb> = build_f(answers);
+        f_ab(build_a(answers))
+    }) }
+}
+
+
+// static analysis: summarises the instructions into any monoid, without
+// running anything.
+pub fn analyze<I, Ta, M: Monoid<T = M>>(ma: &FreeAp<I, Ta>, f: impl Fn(&I) -> M) -> M {
+    fold_map(&ma.instrs, f)
+}
+
+
+// runs the computation, answering each instruction with `step` in order.
+pub fn run_ap<I, Ta>(ma: &FreeAp<I, Ta>, step: impl FnMut(&I) -> Answer) -> Ta {
+    let answers: Vec<Answer> = ma.instrs.iter().map(step).collect();
+    (ma.build)(&mut answers.into_iter())
+}
+
+
+// runs the computation as a reader, each instruction answered by `handler`
+// from the same configuration.
+#[cfg(feature = "reader")]
+pub fn free_ap_reader<I: 'static, Tcfg: 'static, Ta: 'static>(
+    handler: impl Fn(&I) -> ReaderMonad<Tcfg, Answer> + 'static,
+    ma: FreeAp<I, Ta>
+) -> ReaderMonad<Tcfg, Ta> {
+    ReaderMonad { run_reader: Rc::new(move |cfg: &Tcfg| -> Ta {
+        run_ap(&ma, |op: &I| -> Answer { (handler(op).run_reader)(cfg) })
+    }) }
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+    use crate::monads::laws::check_functor_laws;
+    use crate::monads::observe::ObserveEq;
+    use crate::monads::prop::Config;
+
+    // command line options, each answered with what it parses to.
+    #[derive(Clone, Debug, PartialEq)]
+    struct Text {
+        name: &'static str,
+        help: &'static str
+    }
+
+    impl Instr for Text {
+        type Output = Option<String>;
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Port {
+        name: &'static str,
+        help: &'static str
+    }
+
+    impl Instr for Port {
+        type Output = Option<u16>;
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Switch {
+        name: &'static str,
+        help: &'static str
+    }
+
+    impl Instr for Switch {
+        type Output = bool;
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    enum Opt {
+        Text(Text),
+        Port(Port),
+        Switch(Switch)
+    }
+
+    impl From<Text> for Opt {
+        fn from(op: Text) -> Self { Opt::Text(op) }
+    }
+
+    impl From<Port> for Opt {
+        fn from(op: Port) -> Self { Opt::Port(op) }
+    }
+
+    impl From<Switch> for Opt {
+        fn from(op: Switch) -> Self { Opt::Switch(op) }
+    }
+
+    impl Opt {
+        fn name(&self) -> &'static str {
+            match self { Opt::Text(o) => o.name, Opt::Port(o) => o.name, Opt::Switch(o) => o.name }
+        }
+
+        fn help(&self) -> &'static str {
+            match self { Opt::Text(o) => o.help, Opt::Port(o) => o.help, Opt::Switch(o) => o.help }
+        }
+
+        // answers from the raw value of the option, if it was given.
+        fn parse(&self, raw: Option<String>) -> Answer {
+            match self {
+                Opt::Text(o) => o.answer(raw),
+                Opt::Port(o) => o.answer(raw.and_then(|p| p.parse::<u16>().ok())),
+                Opt::Switch(o) => o.answer(raw.is_some())
+            }
+        }
+    }
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Settings {
+        host: String,
+        port: u16,
+        verbose: bool
+    }
+
+    type Parser<Ta> = FreeAp<Opt, Ta>;
+
+    fn settings() -> Parser<Option<Settings>> {
+        type MkSettings = Rc<dyn Fn(Option<String>) -> Rc<dyn Fn(Option<u16>) -> Rc<dyn Fn(bool) -> Option<Settings>>>>;
+        let mk: MkSettings = Rc::new(|host: Option<String>| Rc::new(move |port: Option<u16>| {
+            let host: Option<String> = host.clone();
+            Rc::new(move |verbose: bool| Some(Settings { host: host.clone()?, port: port?, verbose }))
+        }));
+        free_ap_apply(
+            free_ap_apply(
+                free_ap_fmap(mk, lift_ap(Text { name: "host", help: "the host to connect to" })),
+                lift_ap(Port { name: "port", help: "the port to connect to" })
+            ),
+            lift_ap(Switch { name: "verbose", help: "log every request" })
+        )
+    }
+
+    #[test]
+    fn test_analyze() {
+        let names: Vec<String> = analyze(&settings(), |o: &Opt| vec![o.name().to_string()]);
+        assert_eq!(names, vec!["host".to_string(), "port".to_string(), "verbose".to_string()]);
+
+        let help: String = analyze(&settings(), |o: &Opt| format!("--{:<8} {}", o.name(), o.help()));
+        assert_eq!(help, "--host     the host to connect to\n--port     the port to connect to\n--verbose  log every request");
+
+        assert_eq!(analyze(&free_ap_unit::<Opt, i64>(0), |o: &Opt| vec![o.name()]), Vec::<&str>::new());
+    }
+
+    #[test]
+    fn test_run() {
+        let args: Vec<(&str, &str)> = vec![("host", "localhost"), ("port", "8080")];
+        let lookup = |o: &Opt| -> Answer { o.parse(args.iter().find(|(k, _)| *k == o.name()).map(|(_, v)| v.to_string())) };
+        assert_eq!(
+            run_ap(&settings(), lookup),
+            Some(Settings { host: "localhost".to_string(), port: 8080, verbose: false })
+        );
+        assert_eq!(run_ap(&settings(), |o: &Opt| o.parse(None)), None);
+    }
+
+    #[test]
+    #[cfg(feature = "reader")]
+    fn test_reader() {
+        use alloc::collections::BTreeMap;
+
+        type Env = BTreeMap<String, String>;
+        let m: ReaderMonad<Env, Option<Settings>> = free_ap_reader(
+            |o: &Opt| -> ReaderMonad<Env, Answer> {
+                let o: Opt = o.clone();
+                ReaderMonad { run_reader: Rc::new(move |env: &Env| -> Answer { o.parse(env.get(o.name()).cloned()) }) }
+            },
+            settings()
+        );
+        let env: Env = [("host", "example.org"), ("port", "443"), ("verbose", "")]
+            .into_iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        assert_eq!((m.run_reader)(&env), Some(Settings { host: "example.org".to_string(), port: 443, verbose: true }));
+        assert_eq!((m.run_reader)(&Env::new()), None);
+    }
+
+    // an instruction answered by position in a list of answers.
+    #[derive(Clone)]
+    struct Slot(usize);
+
+    impl Instr for Slot {
+        type Output = i64;
+    }
+
+    // observed by running on an arbitrary list of answers, indexed by instruction.
+    struct Indexed(FreeAp<Slot, i64>);
+
+    impl ObserveEq for Indexed {
+        type Input = Vec<i64>;
+        type Output = (i64, Vec<usize>);
+        fn observe(&self, answers: &Vec<i64>) -> Self::Output {
+            let a: i64 = run_ap(&self.0, |i: &Slot| i.answer(answers.get(i.0).copied().unwrap_or(0)));
+            (a, self.0.instrs.iter().map(|i: &Slot| i.0).collect())
+        }
+    }
+
+    #[test]
+    fn test_laws() {
+        fn gen(p: &(usize, usize, i64)) -> Indexed {
+            let c: i64 = p.2;
+            type Curried = Rc<dyn Fn(i64) -> Rc<dyn Fn(i64) -> i64>>;
+            let sum: Curried = Rc::new(move |x: i64| Rc::new(move |y: i64| x.wrapping_sub(y).wrapping_add(c)));
+            Indexed(free_ap_apply(free_ap_fmap(sum, lift_ap(Slot(p.0 % 4))), lift_ap(Slot(p.1 % 4))))
+        }
+        check_functor_laws(&Config::default(), gen, |f, m| Indexed(free_ap_fmap(f, m.0))).unwrap();
+    }
+}
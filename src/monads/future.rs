@@ -0,0 +1,505 @@
+#[cfg(any(test, feature = "testing"))]
+use core::fmt::Debug;
+use core::future::Future;
+use core::pin::{Pin, pin};
+use core::task::{Context, Poll, Waker};
+use alloc::boxed::Box;
+use alloc::rc::Rc;
+#[cfg(feature = "std")]
+use alloc::sync::Arc;
+#[cfg(feature = "std")]
+use std::task::Wake;
+#[cfg(feature = "std")]
+use std::thread::{self, Thread};
+
+use super::monoid::Monoid;
+#[cfg(any(test, feature = "testing"))]
+use super::observe::ObserveEq;
+#[cfg(any(test, feature = "testing"))]
+use super::prop::Arbitrary;
+
+
+// State, reader and writer computations whose steps are futures.
+// the transformers in `state`, `reader` and `writer` cannot take a future
+// as their base: a `Monad` base is only lent its continuation for the call,
+// and a future has to keep it until it is polled again. here every
+// computation runs to a future instead, and the continuation of a bind is
+// itself async, so that in `async_do!` any step may `.await`. the futures
+// are plain `Future`s, not tied to any executor; `block_on` is a minimal one
+// for programs and tests that have no other.
+pub type BoxFuture<Ta> = Pin<Box<dyn Future<Output = Ta>>>;
+
+pub fn boxed<Ta>(fut: impl Future<Output = Ta> + 'static) -> BoxFuture<Ta> {
+    Box::pin(fut)
+}
+
+
+// StateT s Future a
+pub struct AsyncState<Ts, Ta> {
+    pub run_state: Rc<dyn Fn(Ts) -> BoxFuture<(Ta, Ts)>>
+}
+
+// ReaderT r Future a. the configuration is shared rather than lent, since
+// the futures may outlive the call that made them.
+pub struct AsyncReader<Tcfg, Ta> {
+    pub run_reader: Rc<dyn Fn(Rc<Tcfg>) -> BoxFuture<Ta>>
+}
+
+// WriterT w Future a
+pub struct AsyncWriter<Ta, Tlog> {
+    pub run_writer: Rc<dyn Fn() -> BoxFuture<(Ta, Tlog)>>
+}
+
+impl<Ts, Ta> Clone for AsyncState<Ts, Ta> {
+    fn clone(&self) -> Self { AsyncState { run_state: self.run_state.clone() } }
+}
+
+impl<Tcfg, Ta> Clone for AsyncReader<Tcfg, Ta> {
+    fn clone(&self) -> Self { AsyncReader { run_reader: self.run_reader.clone() } }
+}
+
+impl<Ta, Tlog> Clone for AsyncWriter<Ta, Tlog> {
+    fn clone(&self) -> Self { AsyncWriter { run_writer: self.run_writer.clone() } }
+}
+
+
+// the computations are observed by running them to the end.
+#[cfg(any(test, feature = "testing"))]
+impl<Ts: Arbitrary + PartialEq, Ta: PartialEq + Debug> ObserveEq for AsyncState<Ts, Ta> {
+    type Input = Ts;
+    type Output = (Ta, Ts);
+    fn observe(&self, s: &Ts) -> Self::Output { block_on((self.run_state)(s.clone())) }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl<Tcfg: Arbitrary, Ta: PartialEq + Debug> ObserveEq for AsyncReader<Tcfg, Ta> {
+    type Input = Tcfg;
+    type Output = Ta;
+    fn observe(&self, cfg: &Tcfg) -> Self::Output { block_on((self.run_reader)(Rc::new(cfg.clone()))) }
+}
+
+#[cfg(any(test, feature = "testing"))]
+impl<Ta: PartialEq + Debug, Tlog: PartialEq + Debug> ObserveEq for AsyncWriter<Ta, Tlog> {
+    type Input = ();
+    type Output = (Ta, Tlog);
+    fn observe(&self, _: &()) -> Self::Output { block_on((self.run_writer)()) }
+}
+
+
+// the async state monad.
+pub fn async_state_unit<Ts: 'static, Ta: Clone + 'static>(a: Ta) -> AsyncState<Ts, Ta> {
+    AsyncState { run_state: Rc::new(move |s: Ts| -> BoxFuture<(Ta, Ts)> { boxed(core::future::ready((a.clone(), s))) }) }
+}
+
+pub fn async_state_fmap<Ts: 'static, Ta: 'static, Tb: 'static>(
+    f_ab: Rc<dyn Fn(Ta) -> Tb>,
+    ma: AsyncState<Ts, Ta>
+) -> AsyncState<Ts, Tb> {
+    AsyncState { run_state: Rc::new(move |s: Ts| -> BoxFuture<(Tb, Ts)> {
+        let f_ab: Rc<dyn Fn(Ta) -> Tb> = f_ab.clone();
+        let ma: AsyncState<Ts, Ta> = ma.clone();
+        boxed(async move {
+            let (a, s): (Ta, Ts) = (ma.run_state)(s).await;
+            (f_ab(a), s)
+        })
+    }) }
+}
+
+pub fn async_state_apply<Ts: 'static, Ta: 'static, Tb: 'static>(
+    mf: AsyncState<Ts, Rc<dyn Fn(Ta) -> Tb>>,
+    ma: AsyncState<Ts, Ta>
+) -> AsyncState<Ts, Tb> {
+    async_state_bind(mf, AsyncStateKleisli { kleisli: Rc::new(move |f_ab: Rc<dyn Fn(Ta) -> Tb>| -> BoxFuture<AsyncState<Ts, Tb>> {
+        boxed(core::future::ready(async_state_fmap(f_ab, ma.clone())))
+    }) })
+}
+
+// the continuation is async: it may await before choosing what comes next.
+pub struct AsyncStateKleisli<Ts, Ta, Tb> {
+    pub kleisli: Rc<dyn Fn(Ta) -> BoxFuture<AsyncState<Ts, Tb>>>
+}
+
+impl<Ts, Ta, Tb> Clone for AsyncStateKleisli<Ts, Ta, Tb> {
+    fn clone(&self) -> Self { AsyncStateKleisli { kleisli: self.kleisli.clone() } }
+}
+
+pub fn async_state_bind<Ts: 'static, Ta: 'static, Tb: 'static>(
+    ma: AsyncState<Ts, Ta>,
+    k_ab: AsyncStateKleisli<Ts, Ta, Tb>
+) -> AsyncState<Ts, Tb> {
+    AsyncState { run_state: Rc::new(move |s: Ts| -> BoxFuture<(Tb, Ts)> {
+        let (ma, k_ab): (AsyncState<Ts, Ta>, AsyncStateKleisli<Ts, Ta, Tb>) = (ma.clone(), k_ab.clone());
+        boxed(async move {
+            let (a, s): (Ta, Ts) = (ma.run_state)(s).await;
+            let mb: AsyncState<Ts, Tb> = (k_ab.kleisli)(a).await;
+            (mb.run_state)(s).await
+        })
+    }) }
+}
+
+// lift :: m a -> StateT s m a
+// the future is made anew each time the computation runs.
+pub fn async_state_lift<Ts: 'static, Ta: 'static, F: Future<Output = Ta> + 'static>(
+    fut: impl Fn() -> F + 'static
+) -> AsyncState<Ts, Ta> {
+    let fut: Rc<dyn Fn() -> F> = Rc::new(fut);
+    AsyncState { run_state: Rc::new(move |s: Ts| -> BoxFuture<(Ta, Ts)> {
+        let fut: F = fut();
+        boxed(async move { (fut.await, s) })
+    }) }
+}
+
+pub fn async_get<Ts: Clone + 'static>() -> AsyncState<Ts, Ts> {
+    AsyncState { run_state: Rc::new(|s: Ts| -> BoxFuture<(Ts, Ts)> { boxed(core::future::ready((s.clone(), s))) }) }
+}
+
+pub fn async_put<Ts: Clone + 'static>(s: Ts) -> AsyncState<Ts, ()> {
+    AsyncState { run_state: Rc::new(move |_: Ts| -> BoxFuture<((), Ts)> { boxed(core::future::ready(((), s.clone()))) }) }
+}
+
+
+// the async reader monad.
+pub fn async_reader_unit<Tcfg: 'static, Ta: Clone + 'static>(a: Ta) -> AsyncReader<Tcfg, Ta> {
+    AsyncReader { run_reader: Rc::new(move |_: Rc<Tcfg>| -> BoxFuture<Ta> { boxed(core::future::ready(a.clone())) }) }
+}
+
+pub fn async_reader_fmap<Tcfg: 'static, Ta: 'static, Tb: 'static>(
+    f_ab: Rc<dyn Fn(Ta) -> Tb>,
+    ma: AsyncReader<Tcfg, Ta>
+) -> AsyncReader<Tcfg, Tb> {
+    AsyncReader { run_reader: Rc::new(move |cfg: Rc<Tcfg>| -> BoxFuture<Tb> {
+        let f_ab: Rc<dyn Fn(Ta) -> Tb> = f_ab.clone();
+        let ma: AsyncReader<Tcfg, Ta> = ma.clone();
+        boxed(async move { f_ab((ma.run_reader)(cfg).await) })
+    }) }
+}
+
+pub fn async_reader_apply<Tcfg: 'static, Ta: 'static, Tb: 'static>(
+    mf: AsyncReader<Tcfg, Rc<dyn Fn(Ta) -> Tb>>,
+    ma: AsyncReader<Tcfg, Ta>
+) -> AsyncReader<Tcfg, Tb> {
+    async_reader_bind(mf, AsyncReaderKleisli { kleisli: Rc::new(move |f_ab: Rc<dyn Fn(Ta) -> Tb>| -> BoxFuture<AsyncReader<Tcfg, Tb>> {
+        boxed(core::future::ready(async_reader_fmap(f_ab, ma.clone())))
+    }) })
+}
+
+pub struct AsyncReaderKleisli<Tcfg, Ta, Tb> {
+    pub kleisli: Rc<dyn Fn(Ta) -> BoxFuture<AsyncReader<Tcfg, Tb>>>
+}
+
+impl<Tcfg, Ta, Tb> Clone for AsyncReaderKleisli<Tcfg, Ta, Tb> {
+    fn clone(&self) -> Self { AsyncReaderKleisli { kleisli: self.kleisli.clone() } }
+}
+
+pub fn async_reader_bind<Tcfg: 'static, Ta: 'static, Tb: 'static>(
+    ma: AsyncReader<Tcfg, Ta>,
+    k_ab: AsyncReaderKleisli<Tcfg, Ta, Tb>
+) -> AsyncReader<Tcfg, Tb> {
+    AsyncReader { run_reader: Rc::new(move |cfg: Rc<Tcfg>| -> BoxFuture<Tb> {
+        let (ma, k_ab): (AsyncReader<Tcfg, Ta>, AsyncReaderKleisli<Tcfg, Ta, Tb>) = (ma.clone(), k_ab.clone());
+        boxed(async move {
+            let a: Ta = (ma.run_reader)(cfg.clone()).await;
+            let mb: AsyncReader<Tcfg, Tb> = (k_ab.kleisli)(a).await;
+            (mb.run_reader)(cfg).await
+        })
+    }) }
+}
+
+// lift :: m a -> ReaderT r m a
+pub fn async_reader_lift<Tcfg: 'static, Ta: 'static, F: Future<Output = Ta> + 'static>(
+    fut: impl Fn() -> F + 'static
+) -> AsyncReader<Tcfg, Ta> {
+    AsyncReader { run_reader: Rc::new(move |_: Rc<Tcfg>| -> BoxFuture<Ta> { boxed(fut()) }) }
+}
+
+pub fn async_asks<Tcfg: 'static, Ta: 'static>(f: impl Fn(&Tcfg) -> Ta + 'static) -> AsyncReader<Tcfg, Ta> {
+    AsyncReader { run_reader: Rc::new(move |cfg: Rc<Tcfg>| -> BoxFuture<Ta> { boxed(core::future::ready(f(&cfg))) }) }
+}
+
+pub fn run_async_reader<Tcfg, Ta>(ma: &AsyncReader<Tcfg, Ta>, cfg: Tcfg) -> BoxFuture<Ta> {
+    (ma.run_reader)(Rc::new(cfg))
+}
+
+
+// the async writer monad.
+pub fn async_writer_unit<Ta: Clone + 'static, Tlog: Monoid<T = Tlog> + 'static>(a: Ta) -> AsyncWriter<Ta, Tlog> {
+    AsyncWriter { run_writer: Rc::new(move || -> BoxFuture<(Ta, Tlog)> { boxed(core::future::ready((a.clone(), Tlog::mempty()))) }) }
+}
+
+pub fn async_writer_fmap<Ta: 'static, Tb: 'static, Tlog: 'static>(
+    f_ab: Rc<dyn Fn(Ta) -> Tb>,
+    ma: AsyncWriter<Ta, Tlog>
+) -> AsyncWriter<Tb, Tlog> {
+    AsyncWriter { run_writer: Rc::new(move || -> BoxFuture<(Tb, Tlog)> {
+        let f_ab: Rc<dyn Fn(Ta) -> Tb> = f_ab.clone();
+        let ma: AsyncWriter<Ta, Tlog> = ma.clone();
+        boxed(async move {
+            let (a, w): (Ta, Tlog) = (ma.run_writer)().await;
+            (f_ab(a), w)
+        })
+    }) }
+}
+
+pub fn async_writer_apply<Ta: 'static, Tb: 'static, Tlog: Monoid<T = Tlog> + 'static>(
+    mf: AsyncWriter<Rc<dyn Fn(Ta) -> Tb>, Tlog>,
+    ma: AsyncWriter<Ta, Tlog>
+) -> AsyncWriter<Tb, Tlog> {
+    async_writer_bind(mf, AsyncWriterKleisli { kleisli: Rc::new(move |f_ab: Rc<dyn Fn(Ta) -> Tb>| -> BoxFuture<AsyncWriter<Tb, Tlog>> {
+        boxed(core::future::ready(async_writer_fmap(f_ab, ma.clone())))
+    }) })
+}
+
+pub struct AsyncWriterKleisli<Ta, Tb, Tlog> {
+    pub kleisli: Rc<dyn Fn(Ta) -> BoxFuture<AsyncWriter<Tb, Tlog>>>
+}
+
+impl<Ta, Tb, Tlog> Clone for AsyncWriterKleisli<Ta, Tb, Tlog> {
+    fn clone(&self) -> Self { AsyncWriterKleisli { kleisli: self.kleisli.clone() } }
+}
+
+pub fn async_writer_bind<Ta: 'static, Tb: 'static, Tlog: Monoid<T = Tlog> + 'static>(
+    ma: AsyncWriter<Ta, Tlog>,
+    k_ab: AsyncWriterKleisli<Ta, Tb, Tlog>
+) -> AsyncWriter<Tb, Tlog> {
+    AsyncWriter { run_writer: Rc::new(move || -> BoxFuture<(Tb, Tlog)> {
+        let (ma, k_ab): (AsyncWriter<Ta, Tlog>, AsyncWriterKleisli<Ta, Tb, Tlog>) = (ma.clone(), k_ab.clone());
+        boxed(async move {
+            let (a, mut w): (Ta, Tlog) = (ma.run_writer)().await;
+            let mb: AsyncWriter<Tb, Tlog> = (k_ab.kleisli)(a).await;
+            let (b, w_b): (Tb, Tlog) = (mb.run_writer)().await;
+            w.mappend_assign(w_b);
+            (b, w)
+        })
+    }) }
+}
+
+// lift :: m a -> WriterT w m a
+pub fn async_writer_lift<Ta: 'static, Tlog: Monoid<T = Tlog> + 'static, F: Future<Output = Ta> + 'static>(
+    fut: impl Fn() -> F + 'static
+) -> AsyncWriter<Ta, Tlog> {
+    let fut: Rc<dyn Fn() -> F> = Rc::new(fut);
+    AsyncWriter { run_writer: Rc::new(move || -> BoxFuture<(Ta, Tlog)> {
+        let fut: F = fut();
+        boxed(async move { (fut.await, Tlog::mempty()) })
+    }) }
+}
+
+pub fn async_log<Tlog: Clone + 'static>(msg: Tlog) -> AsyncWriter<(), Tlog> {
+    AsyncWriter { run_writer: Rc::new(move || -> BoxFuture<((), Tlog)> { boxed(core::future::ready(((), msg.clone()))) }) }
+}
+
+
+// A minimal executor: polls `fut` on the current thread until it is ready.
+// with `std` the thread sleeps until the future's waker is called; without
+// it, it polls again straight away.
+#[cfg(feature = "std")]
+struct Unpark(Thread);
+
+#[cfg(feature = "std")]
+impl Wake for Unpark {
+    fn wake(self: Arc<Self>) { self.0.unpark() }
+
+    fn wake_by_ref(self: &Arc<Self>) { self.0.unpark() }
+}
+
+pub fn block_on<F: Future>(fut: F) -> F::Output {
+    let mut fut: Pin<&mut F> = pin!(fut);
+    #[cfg(feature = "std")]
+    let waker: Waker = Waker::from(Arc::new(Unpark(thread::current())));
+    #[cfg(not(feature = "std"))]
+    let waker: Waker = Waker::noop().clone();
+    let mut cx: Context = Context::from_waker(&waker);
+    loop {
+        match fut.as_mut().poll(&mut cx) {
+            Poll::Ready(a) => return a,
+            #[cfg(feature = "std")]
+            Poll::Pending => thread::park(),
+            #[cfg(not(feature = "std"))]
+            Poll::Pending => core::hint::spin_loop()
+        }
+    }
+}
+
+
+// do-notation, over futures. the target comes first, as in
+// `async_do!(state => ...)`, `async_do!(reader => ...)` or
+// `async_do!(writer => ...)`. every step after the first runs in an async
+// block, so any expression in it may `.await`; the whole body does too,
+// behind a bind on `()`. internally every rule carries the module, the bind
+// and the kleisli arrow of the target.
+#[macro_export]
+macro_rules! async_do {
+    (state => $($rest:tt)*) => {
+        $crate::async_do!(@start[$crate::monads::future][async_state_unit][async_state_bind][AsyncStateKleisli] $($rest)*)
+    };
+    (reader => $($rest:tt)*) => {
+        $crate::async_do!(@start[$crate::monads::future][async_reader_unit][async_reader_bind][AsyncReaderKleisli] $($rest)*)
+    };
+    (writer => $($rest:tt)*) => {
+        $crate::async_do!(@start[$crate::monads::future][async_writer_unit][async_writer_bind][AsyncWriterKleisli] $($rest)*)
+    };
+
+    (@start[$($m:tt)*][$unit:ident][$bind:ident][$k:ident] $($rest:tt)*) => {
+        $($m)*::$bind(
+            $($m)*::$unit(()),
+            $($m)*::$k {
+                kleisli: $crate::prelude::Rc::new( move |_| { $($m)*::boxed(async move { $crate::async_do!(@[$($m)*][$bind][$k] $($rest)*) }) } )
+            }
+        )
+    };
+
+    // trailing comma
+    (@[$($m:tt)*][$bind:ident][$k:ident]) => {};
+
+    (@[$($m:tt)*][$bind:ident][$k:ident] $v:ident = $e:expr,  $($rest:tt)*) => { { let $v = $e; $crate::async_do!(@[$($m)*][$bind][$k] $($rest)*) } };
+
+    (@[$($m:tt)*][$bind:ident][$k:ident] $v:ident <- $e:expr, $($rest:tt)*) => {
+        $($m)*::$bind(
+            $e,
+            $($m)*::$k {
+                kleisli: $crate::prelude::Rc::new( move |$v| { $($m)*::boxed(async move { $crate::async_do!(@[$($m)*][$bind][$k] $($rest)*) }) } )
+            }
+        )
+    };
+
+    (@[$($m:tt)*][$bind:ident][$k:ident] $e:expr, $($rest:tt)*) => {
+        $($m)*::$bind(
+            $e,
+            $($m)*::$k {
+                kleisli: $crate::prelude::Rc::new( move |_| { $($m)*::boxed(async move { $crate::async_do!(@[$($m)*][$bind][$k] $($rest)*) }) } )
+            }
+        )
+    };
+
+    (@[$($m:tt)*][$bind:ident][$k:ident] $e:expr) => { $e };
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::{String, ToString};
+    use alloc::format;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use std::sync::{Arc, Mutex};
+    use crate::monads::laws::{check_functor_laws, check_monad_laws};
+    use crate::monads::prop::Config;
+
+    // pending on its first poll, as a future waiting on something would be.
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context) -> Poll<()> {
+            if self.0 { return Poll::Ready(()) }
+            self.0 = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    async fn fetch(x: i64) -> i64 {
+        YieldOnce(false).await;
+        x.wrapping_mul(2)
+    }
+
+    // ready once another thread has filled it in.
+    struct Slot(Arc<Mutex<(Option<i64>, Option<Waker>)>>);
+
+    impl Future for Slot {
+        type Output = i64;
+
+        fn poll(self: Pin<&mut Self>, cx: &mut Context) -> Poll<i64> {
+            let mut slot = self.0.lock().unwrap();
+            match slot.0 {
+                Some(x) => Poll::Ready(x),
+                None => {
+                    slot.1 = Some(cx.waker().clone());
+                    Poll::Pending
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_block_on() {
+        assert_eq!(block_on(fetch(21)), 42);
+
+        let shared: Arc<Mutex<(Option<i64>, Option<Waker>)>> = Arc::new(Mutex::new((None, None)));
+        let filler: Arc<Mutex<(Option<i64>, Option<Waker>)>> = shared.clone();
+        let worker = std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            let mut slot = filler.lock().unwrap();
+            slot.0 = Some(7);
+            if let Some(waker) = slot.1.take() { waker.wake() }
+        });
+        assert_eq!(block_on(Slot(shared)), 7);
+        worker.join().unwrap();
+    }
+
+    #[test]
+    fn test_state() {
+        let counter: AsyncState<i64, i64> = async_do!(state =>
+            n <- async_get(),
+            doubled = fetch(n).await,
+            async_put(doubled + 1),
+            m <- async_get(),
+            async_state_unit(m * 10)
+        );
+        assert_eq!(block_on((counter.run_state)(3)), (70, 7));
+        // the computation can be run again, with another state.
+        assert_eq!(block_on((counter.run_state)(0)), (10, 1));
+
+        let lifted: AsyncState<i64, i64> = async_state_lift(|| fetch(5));
+        assert_eq!(block_on((lifted.run_state)(1)), (10, 1));
+    }
+
+    struct Env {
+        base: i64,
+        name: String
+    }
+
+    #[test]
+    fn test_reader() {
+        let greeting: AsyncReader<Env, String> = async_do!(reader =>
+            base <- async_asks(|cfg: &Env| cfg.base),
+            x = fetch(base).await,
+            name <- async_asks(|cfg: &Env| cfg.name.clone()),
+            async_reader_unit(format!("{} {}", name, x))
+        );
+        let cfg: Env = Env { base: 4, name: "n".to_string() };
+        assert_eq!(block_on(run_async_reader(&greeting, cfg)), "n 8");
+    }
+
+    #[test]
+    fn test_writer() {
+        let logged: AsyncWriter<i64, Vec<String>> = async_do!(writer =>
+            async_log(vec!["start".to_string()]),
+            x <- async_writer_lift(|| fetch(1)),
+            y = fetch(x).await,
+            async_log(vec![format!("got {}", y)]),
+            async_writer_unit(y + 1)
+        );
+        assert_eq!(block_on((logged.run_writer)()), (5, vec!["start".to_string(), "got 4".to_string()]));
+    }
+
+    #[test]
+    fn test_laws() {
+        let gen = |p: &(i64, i64)| -> AsyncState<i64, i64> {
+            let (a, b): (i64, i64) = *p;
+            AsyncState { run_state: Rc::new(move |s: i64| -> BoxFuture<(i64, i64)> {
+                boxed(async move { (fetch(a).await, s.wrapping_add(b)) })
+            }) }
+        };
+        check_functor_laws(&Config::default(), gen, async_state_fmap).unwrap();
+        check_monad_laws(
+            &Config::default(),
+            gen,
+            async_state_unit,
+            |m, k| async_state_bind(m, AsyncStateKleisli { kleisli: Rc::new(move |a: i64| boxed(core::future::ready(k(a)))) })
+        ).unwrap();
+    }
+}
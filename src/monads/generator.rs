@@ -0,0 +1,348 @@
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+use super::cont::ContMonad;
+
+
+// Generators and coroutines.
+// all are continuation monads whose answer stops at every `yield_` (or
+// `suspend`, or `transfer`) with the value handed out and the continuation
+// that carries on from there. the driver keeps that continuation and calls
+// it when asked for the next value, so the body reads as straight-line code
+// in `cont_do!` and the state machine it replaces is built by `cont_bind`.
+// a `Coroutine` is asymmetric: `suspend` always goes back to whoever called
+// `resume`. symmetric coroutines pass control to one another by name with
+// `transfer`, and `run_symmetric` only carries out the jumps.
+
+
+// a generator body: yields `Ty`s, and is done when it returns.
+pub type Gen<Ty, Ta> = ContMonad<Step<Ty>, Ta>;
+
+pub type Thunk<Ty> = Rc<dyn Fn() -> Step<Ty>>;
+
+pub enum Step<Ty> {
+    Done,
+    Yield(Ty, Thunk<Ty>)
+}
+
+
+// hands `y` out, carrying on when the next value is asked for.
+pub fn yield_<Ty: Clone + 'static>(y: Ty) -> Gen<Ty, ()> {
+    ContMonad { run_cont: Rc::new(move |k: Rc<dyn Fn(()) -> Step<Ty>>| -> Step<Ty> {
+        Step::Yield(y.clone(), Rc::new(move || -> Step<Ty> { k(()) }))
+    }) }
+}
+
+
+// the values of a generator body, as an iterator. nothing runs until the
+// first call to `next`, and each call runs the body up to its next `yield_`
+// only, so the body may go on forever.
+pub struct Generator<Ty> {
+    next: Option<Thunk<Ty>>
+}
+
+impl<Ty> Clone for Generator<Ty> {
+    fn clone(&self) -> Self { Generator { next: self.next.clone() } }
+}
+
+pub fn generator<Ty: 'static, Ta: 'static>(body: Gen<Ty, Ta>) -> Generator<Ty> {
+    Generator { next: Some(Rc::new(move || -> Step<Ty> { (body.run_cont)(Rc::new(|_: Ta| Step::Done)) })) }
+}
+
+impl<Ty> Iterator for Generator<Ty> {
+    type Item = Ty;
+
+    fn next(&mut self) -> Option<Ty> {
+        let thunk: Thunk<Ty> = self.next.take()?;
+        match thunk() {
+            Step::Done => None,
+            Step::Yield(y, rest) => {
+                self.next = Some(rest);
+                Some(y)
+            }
+        }
+    }
+}
+
+
+// a coroutine body: suspends with `Tout`s, is resumed with `Tin`s, and
+// finishes with a `Tr`.
+pub type Co<Tin, Tout, Tr, Ta> = ContMonad<CoStep<Tin, Tout, Tr>, Ta>;
+
+pub type Resumption<Tin, Tout, Tr> = Rc<dyn Fn(Tin) -> CoStep<Tin, Tout, Tr>>;
+
+pub enum CoStep<Tin, Tout, Tr> {
+    Finished(Tr),
+    Suspended(Tout, Resumption<Tin, Tout, Tr>)
+}
+
+// what a coroutine did with the value it was resumed with.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CoState<Tout, Tr> {
+    Suspended(Tout),
+    Finished(Tr)
+}
+
+
+// hands `out` to whoever resumed the coroutine, and waits to be resumed
+// with the next input.
+pub fn suspend<Tin: 'static, Tout: Clone + 'static, Tr: 'static>(out: Tout) -> Co<Tin, Tout, Tr, Tin> {
+    ContMonad { run_cont: Rc::new(move |k: Resumption<Tin, Tout, Tr>| -> CoStep<Tin, Tout, Tr> {
+        CoStep::Suspended(out.clone(), k)
+    }) }
+}
+
+
+pub struct Coroutine<Tin, Tout, Tr> {
+    next: Option<Resumption<Tin, Tout, Tr>>
+}
+
+impl<Tin, Tout, Tr> Clone for Coroutine<Tin, Tout, Tr> {
+    fn clone(&self) -> Self { Coroutine { next: self.next.clone() } }
+}
+
+// the body is given the input of the first `resume`.
+pub fn coroutine<Tin: 'static, Tout: 'static, Tr: 'static>(
+    body: impl Fn(Tin) -> Co<Tin, Tout, Tr, Tr> + 'static
+) -> Coroutine<Tin, Tout, Tr> {
+    Coroutine { next: Some(Rc::new(move |x: Tin| -> CoStep<Tin, Tout, Tr> {
+        (body(x).run_cont)(Rc::new(CoStep::Finished))
+    })) }
+}
+
+impl<Tin, Tout, Tr> Coroutine<Tin, Tout, Tr> {
+    // runs the coroutine with `x` up to its next `suspend`, or to its end.
+    pub fn resume(&mut self, x: Tin) -> CoState<Tout, Tr> {
+        let k: Resumption<Tin, Tout, Tr> = self.next.take().expect("coroutine: resumed after it finished");
+        match k(x) {
+            CoStep::Finished(r) => CoState::Finished(r),
+            CoStep::Suspended(out, rest) => {
+                self.next = Some(rest);
+                CoState::Suspended(out)
+            }
+        }
+    }
+
+    pub fn is_finished(&self) -> bool { self.next.is_none() }
+}
+
+
+// symmetric coroutines: bodies that pass a `Tv` and control to one another.
+// the first to finish ends the run with its `Tr`.
+pub type Sym<Tv, Tr, Ta> = ContMonad<Transfer<Tv, Tr>, Ta>;
+
+pub type SymBody<Tv, Tr> = Rc<dyn Fn(Tv) -> Sym<Tv, Tr, Tr>>;
+
+pub type SymResumption<Tv, Tr> = Rc<dyn Fn(Tv) -> Transfer<Tv, Tr>>;
+
+pub enum Transfer<Tv, Tr> {
+    Finished(Tr),
+    // the coroutine to run next, what it is given, and where this one carries on.
+    To(usize, Tv, SymResumption<Tv, Tr>)
+}
+
+
+// hands `v` to coroutine `to`, and waits until one transfers back to this
+// one, with the value it gives. a coroutine may transfer to itself.
+pub fn transfer<Tv: Clone + 'static, Tr: 'static>(to: usize, v: Tv) -> Sym<Tv, Tr, Tv> {
+    ContMonad { run_cont: Rc::new(move |k: SymResumption<Tv, Tr>| -> Transfer<Tv, Tr> {
+        Transfer::To(to, v.clone(), k)
+    }) }
+}
+
+
+// runs `bodies`, known to each other by their index, starting `first` with
+// `v`. a body that has not run yet starts from the top when transferred to.
+// every transfer comes back here before the next coroutine runs, so however
+// long they go back and forth the stack does not grow.
+pub fn run_symmetric<Tv: 'static, Tr: 'static>(bodies: Vec<SymBody<Tv, Tr>>, first: usize, v: Tv) -> Tr {
+    let mut waiting: Vec<SymResumption<Tv, Tr>> = bodies.into_iter().map(|body: SymBody<Tv, Tr>| -> SymResumption<Tv, Tr> {
+        Rc::new(move |v: Tv| -> Transfer<Tv, Tr> { (body(v).run_cont)(Rc::new(Transfer::Finished)) })
+    }).collect();
+    let mut current: usize = first;
+    let mut step: Transfer<Tv, Tr> = waiting[current](v);
+    loop {
+        match step {
+            Transfer::Finished(r) => return r,
+            Transfer::To(to, v, rest) => {
+                // kept before `to` is looked up, so that a transfer to itself finds it.
+                waiting[current] = rest;
+                current = to;
+                step = waiting[current].clone()(v)
+            }
+        }
+    }
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use crate::cont_do;
+    use crate::monads::cont::cont_unit;
+
+    fn naturals(n: u64) -> Gen<u64, ()> {
+        cont_do!(
+            yield_(n),
+            naturals(n + 1)
+        )
+    }
+
+    fn fibonacci(a: u64, b: u64) -> Gen<u64, ()> {
+        cont_do!(
+            yield_(a),
+            fibonacci(b, a + b)
+        )
+    }
+
+    #[test]
+    fn test_generator() {
+        let evens: Vec<u64> = generator(naturals(0)).filter(|n| n % 2 == 0).take(4).collect();
+        assert_eq!(evens, vec![0, 2, 4, 6]);
+
+        let fibs: Vec<u64> = generator(fibonacci(0, 1)).take(10).collect();
+        assert_eq!(fibs, vec![0, 1, 1, 2, 3, 5, 8, 13, 21, 34]);
+
+        let finite: Gen<&str, ()> = cont_do!(yield_("a"), yield_("b"), cont_unit(()));
+        assert_eq!(generator(finite).collect::<Vec<&str>>(), vec!["a", "b"]);
+
+        // each step only runs up to the next yield, so there is no stack to grow.
+        assert_eq!(generator(naturals(0)).nth(100_000), Some(100_000));
+    }
+
+    enum Tree {
+        Leaf,
+        Node(&'static Tree, i64, &'static Tree)
+    }
+
+    static TREE: Tree = Tree::Node(
+        &Tree::Node(&Tree::Leaf, 1, &Tree::Node(&Tree::Leaf, 2, &Tree::Leaf)),
+        3,
+        &Tree::Node(&Tree::Leaf, 4, &Tree::Leaf)
+    );
+
+    // an in-order walk, written as the recursion it is.
+    fn walk(t: &'static Tree) -> Gen<i64, ()> {
+        match t {
+            Tree::Leaf => cont_unit(()),
+            Tree::Node(l, x, r) => cont_do!(
+                walk(l),
+                yield_(*x),
+                walk(r)
+            )
+        }
+    }
+
+    #[test]
+    fn test_walk() {
+        assert_eq!(generator(walk(&TREE)).collect::<Vec<i64>>(), vec![1, 2, 3, 4]);
+        assert_eq!(generator(walk(&TREE)).zip(generator(naturals(10))).last(), Some((4, 13)));
+    }
+
+    // keeps a running average of the numbers it is resumed with, and
+    // finishes with their count when resumed with `None`.
+    fn averager(sum: f64, n: u64, x: Option<f64>) -> Co<Option<f64>, f64, u64, u64> {
+        match x {
+            None => cont_unit(n),
+            Some(x) => {
+                let (sum, n): (f64, u64) = (sum + x, n + 1);
+                cont_do!(
+                    next <- suspend(sum / n as f64),
+                    averager(sum, n, next)
+                )
+            }
+        }
+    }
+
+    #[test]
+    fn test_coroutine() {
+        let mut avg: Coroutine<Option<f64>, f64, u64> = coroutine(|x: Option<f64>| averager(0.0, 0, x));
+        assert_eq!(avg.resume(Some(2.0)), CoState::Suspended(2.0));
+        assert_eq!(avg.resume(Some(4.0)), CoState::Suspended(3.0));
+        assert_eq!(avg.resume(Some(9.0)), CoState::Suspended(5.0));
+        assert!(!avg.is_finished());
+        assert_eq!(avg.resume(None), CoState::Finished(3));
+        assert!(avg.is_finished());
+    }
+
+    // two coroutines passing a message back and forth, each adding its mark.
+    fn player(mark: &'static str, rounds: usize, msg: String) -> Co<String, String, String, String> {
+        if rounds == 0 { return cont_unit(msg) }
+        let msg: String = msg + mark;
+        cont_do!(
+            reply <- suspend(msg),
+            player(mark, rounds - 1, reply)
+        )
+    }
+
+    #[test]
+    fn test_ping_pong() {
+        let mut ping: Coroutine<String, String, String> = coroutine(|msg: String| player("ping ", 2, msg));
+        let mut pong: Coroutine<String, String, String> = coroutine(|msg: String| player("pong ", 2, msg));
+        let mut msg: String = "".to_string();
+        let mut ping_turn: bool = true;
+        let last: String = loop {
+            let co: &mut Coroutine<String, String, String> = if ping_turn { &mut ping } else { &mut pong };
+            match co.resume(msg) {
+                CoState::Suspended(out) => msg = out,
+                CoState::Finished(out) => break out
+            }
+            ping_turn = !ping_turn;
+        };
+        assert_eq!(last, "ping pong ping pong ");
+    }
+
+    // the same game without a driver: each player hands the message
+    // straight to the other.
+    fn sym_player(other: usize, mark: &'static str, rounds: usize, msg: String) -> Sym<String, String, String> {
+        if rounds == 0 { return cont_unit(msg) }
+        let msg: String = msg + mark;
+        cont_do!(
+            reply <- transfer(other, msg),
+            sym_player(other, mark, rounds - 1, reply)
+        )
+    }
+
+    #[test]
+    fn test_symmetric_ping_pong() {
+        let ping: SymBody<String, String> = Rc::new(|msg: String| sym_player(1, "ping ", 2, msg));
+        let pong: SymBody<String, String> = Rc::new(|msg: String| sym_player(0, "pong ", 2, msg));
+        assert_eq!(run_symmetric(vec![ping.clone(), pong.clone()], 0, "".to_string()), "ping pong ping pong ");
+        assert_eq!(run_symmetric(vec![ping, pong], 1, "".to_string()), "pong ping pong ping ");
+    }
+
+    // passes a count on to `next` until it reaches `limit`.
+    fn relay(next: usize, limit: u64, n: u64) -> Sym<u64, u64, u64> {
+        if n >= limit { return cont_unit(n) }
+        cont_do!(
+            m <- transfer(next, n + 1),
+            relay(next, limit, m)
+        )
+    }
+
+    #[test]
+    fn test_symmetric_ring() {
+        let ring = |limit: u64| -> Vec<SymBody<u64, u64>> {
+            (0..3).map(|i: usize| -> SymBody<u64, u64> { Rc::new(move |n: u64| relay((i + 1) % 3, limit, n)) }).collect()
+        };
+        assert_eq!(run_symmetric(ring(5), 0, 0), 5);
+        // every transfer goes back through `run_symmetric`, so there is no stack to grow.
+        assert_eq!(run_symmetric(ring(100_000), 2, 0), 100_000);
+
+        // a coroutine that transfers to itself simply carries on.
+        let alone: SymBody<u64, u64> = Rc::new(|n: u64| relay(0, 3, n));
+        assert_eq!(run_symmetric(vec![alone], 0, 0), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "resumed after it finished")]
+    fn test_resume_finished() {
+        let mut co: Coroutine<(), (), ()> = coroutine(|_: ()| cont_unit(()));
+        assert_eq!(co.resume(()), CoState::Finished(()));
+        co.resume(());
+    }
+}
@@ -0,0 +1,187 @@
+#[cfg(any(test, feature = "testing"))]
+use core::fmt::Debug;
+use alloc::rc::Rc;
+
+#[cfg(any(test, feature = "testing"))]
+use super::observe::ObserveEq;
+
+
+// A monad as a type-level function `M<_>` together with its operations.
+// Rust has no higher kinded types, so this stands in for the `m` of
+// Haskell's `StateT s m a`: a transformer is generic over one of these
+// and builds its results with `M::unit`/`M::bind`. the continuation given
+// to `bind` is only borrowed for the call, so the bases are the strict
+// monads (`Identity`, `Option`, `Result`, `Vec`, ...).
+pub trait Monad {
+    type M<Ta>;
+
+    fn unit<Ta>(a: Ta) -> Self::M<Ta>;
+
+    fn bind<Ta, Tb>(ma: Self::M<Ta>, k_ab: impl Fn(Ta) -> Self::M<Tb>) -> Self::M<Tb>;
+
+    fn fmap<Ta, Tb>(f_ab: impl Fn(Ta) -> Tb, ma: Self::M<Ta>) -> Self::M<Tb> {
+        Self::bind(ma, |a: Ta| -> Self::M<Tb> { Self::unit(f_ab(a)) })
+    }
+}
+
+
+// The identity monad: a value with nothing around it.
+// it is transparent, so wrapping and `run_identity` compile to nothing.
+#[repr(transparent)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Identity<Ta = ()>(pub Ta);
+
+impl<Ta> Identity<Ta> {
+    // runIdentity :: Identity a -> a
+    #[inline(always)]
+    pub fn run_identity(self) -> Ta { self.0 }
+}
+
+
+// `Identity` is also the base of every transformer stack. there the
+// newtype is dropped, `M<Ta>` is `Ta` itself, and `StateT<Ts, Identity, Ta>`
+// is exactly the plain state monad.
+impl Monad for Identity {
+    type M<Ta> = Ta;
+
+    #[inline(always)]
+    fn unit<Ta>(a: Ta) -> Ta { a }
+
+    #[inline(always)]
+    fn bind<Ta, Tb>(ma: Ta, k_ab: impl Fn(Ta) -> Tb) -> Tb { k_ab(ma) }
+}
+
+
+// there is nothing to run; the value is observed as it is.
+#[cfg(any(test, feature = "testing"))]
+impl<Ta: PartialEq + Debug + Clone> ObserveEq for Identity<Ta> {
+    type Input = ();
+    type Output = Ta;
+    fn observe(&self, _: &()) -> Self::Output { self.0.clone() }
+}
+
+
+pub fn identity_unit<Ta>(a: Ta) -> Identity<Ta> {
+    Identity(a)
+}
+
+
+// functor
+pub fn identity_fmap<Ta, Tb>(f_ab: impl FnOnce(Ta) -> Tb, ma: Identity<Ta>) -> Identity<Tb> {
+    Identity(f_ab(ma.0))
+}
+
+
+// applicative
+pub fn identity_apply<Ta, Tb>(mf: Identity<impl FnOnce(Ta) -> Tb>, ma: Identity<Ta>) -> Identity<Tb> {
+    Identity((mf.0)(ma.0))
+}
+
+
+// monad
+pub struct IdentityKleisli<Ta, Tb> {
+    pub kleisli: Rc<dyn Fn(Ta) -> Identity<Tb>>
+}
+
+impl<Ta, Tb> Clone for IdentityKleisli<Ta, Tb> {
+    fn clone(&self) -> Self { IdentityKleisli { kleisli: self.kleisli.clone() } }
+}
+
+pub fn identity_bind<Ta, Tb>(ma: Identity<Ta>, k_ab: IdentityKleisli<Ta, Tb>) -> Identity<Tb> {
+    (k_ab.kleisli)(ma.0)
+}
+
+
+// do-notation. internally every rule carries the module to take the monad
+// from and the pointer its kleisli arrows are stored in.
+#[macro_export]
+macro_rules! identity_do {
+    // trailing comma
+    (@[$($m:tt)*][$($p:tt)*]) => {};
+
+    (@[$($m:tt)*][$($p:tt)*] $v:ident = $e:expr,  $($rest:tt)*) => { (|$v| { $crate::identity_do!(@[$($m)*][$($p)*] $($rest)*) })($e) };
+
+    (@[$($m:tt)*][$($p:tt)*] $v:ident <- $e:expr, $($rest:tt)*) => {
+        $($m)*::identity_bind(
+            $e,
+            $($m)*::IdentityKleisli {
+                kleisli: $($p)*::new( move |$v| { $crate::identity_do!(@[$($m)*][$($p)*] $($rest)*) } )
+            }
+        )
+    };
+
+    (@[$($m:tt)*][$($p:tt)*] $e:expr, $($rest:tt)*) => {
+        $($m)*::identity_bind(
+            $e,
+            $($m)*::IdentityKleisli {
+                kleisli: $($p)*::new( move |_| { $crate::identity_do!(@[$($m)*][$($p)*] $($rest)*) } )
+            }
+        )
+    };
+
+    (@[$($m:tt)*][$($p:tt)*] $e:expr) => { $e };
+
+    ($($rest:tt)*) => { $crate::identity_do!(@[$crate::monads::identity][$crate::prelude::Rc] $($rest)*) };
+}
+
+
+// tests
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use alloc::string::{String, ToString};
+    use crate::monads::laws::{check_functor_laws, check_monad_laws};
+    use crate::monads::prop::Config;
+
+    // `Option` as a base, for the transformer tests.
+    #[cfg(any(feature = "state", feature = "reader", feature = "writer"))]
+    pub(crate) enum Maybe {}
+
+    #[cfg(any(feature = "state", feature = "reader", feature = "writer"))]
+    impl Monad for Maybe {
+        type M<Ta> = Option<Ta>;
+        fn unit<Ta>(a: Ta) -> Option<Ta> { Some(a) }
+        fn bind<Ta, Tb>(ma: Option<Ta>, k_ab: impl Fn(Ta) -> Option<Tb>) -> Option<Tb> { ma.and_then(k_ab) }
+    }
+
+    #[test]
+    fn test_fmap() {
+        let suffix: String = "!".to_string();
+        assert_eq!(identity_fmap(move |x: String| x + &suffix, Identity("hi".to_string())), Identity("hi!".to_string()));
+    }
+
+    #[test]
+    fn test_apply() {
+        let k: i64 = 3;
+        assert_eq!(identity_apply(Identity(move |x: i64| k * x), identity_unit(7)).run_identity(), 21);
+    }
+
+    #[test]
+    fn test_do() {
+        let m: Identity<i64> = identity_do!(
+            x <- identity_unit(4),
+            y = x * 10,
+            z <- identity_fmap(|a: i64| a + 2, identity_unit(y)),
+            identity_unit(x + z)
+        );
+        assert_eq!(m.run_identity(), 46);
+    }
+
+    #[test]
+    fn test_base() {
+        assert_eq!(<Identity as Monad>::bind(20, |x: i64| x + 1), 21);
+        assert_eq!(<Identity as Monad>::fmap(|x: i64| x * 2, <Identity as Monad>::unit(21)), 42);
+        assert_eq!(core::mem::size_of::<Identity<(i64, i64)>>(), core::mem::size_of::<(i64, i64)>());
+    }
+
+    #[test]
+    fn test_laws() {
+        check_functor_laws(&Config::default(), |a: &i64| Identity(*a), |f, m| identity_fmap(move |a| f(a), m)).unwrap();
+        check_monad_laws(
+            &Config::default(),
+            |a: &i64| Identity(*a),
+            identity_unit,
+            |m, k| identity_bind(m, IdentityKleisli { kleisli: k })
+        ).unwrap();
+    }
+}
@@ -0,0 +1,12 @@
+// Statically dispatched variants of the function-wrapping monads.
+// A computation is a plain closure wrapped in a struct, and every combinator
+// returns a new struct around a new closure, the way iterator adapters do.
+// Chains of binds are therefore a single concrete type that the compiler can
+// monomorphize and inline, with no allocation and no virtual calls. When the
+// type has to be erased (recursion, branches of different shapes, storage),
+// `.boxed()` turns any of them into its `Rc<dyn Fn>` backed `Dyn*` form.
+
+#[cfg(feature = "reader")]
+pub mod reader;
+#[cfg(feature = "state")]
+pub mod state;
@@ -0,0 +1,130 @@
+use alloc::rc::Rc;
+
+
+// A reader &cfg -> a whose closure type is known statically.
+#[derive(Clone, Copy)]
+pub struct Reader<F> {
+    pub run_reader: F
+}
+
+
+// A reader behind an `Rc<dyn Fn>`, for when the type has to be erased.
+pub struct DynReader<Tcfg, Ta> {
+    pub run_reader: Rc<dyn Fn(&Tcfg) -> Ta>
+}
+
+impl<Tcfg, Ta> Clone for DynReader<Tcfg, Ta> {
+    fn clone(&self) -> Self { DynReader { run_reader: self.run_reader.clone() } }
+}
+
+
+// Anything that can be run as a reader. The combinators are provided
+// methods, so `Reader` and `DynReader` chain in exactly the same way.
+pub trait RunReader<Tcfg>: Sized {
+    type Value;
+
+    fn run(&self, cfg: &Tcfg) -> Self::Value;
+
+    // functor
+    fn fmap<Tb>(self, f_ab: impl Fn(Self::Value) -> Tb) -> Reader<impl Fn(&Tcfg) -> Tb> {
+        Reader { run_reader: move |cfg: &Tcfg| -> Tb { f_ab(self.run(cfg)) } }
+    }
+
+    // applicative
+    fn apply<Ma: RunReader<Tcfg>, Tb>(self, ma: Ma) -> Reader<impl Fn(&Tcfg) -> Tb>
+    where
+        Self::Value: FnOnce(Ma::Value) -> Tb
+    {
+        Reader { run_reader: move |cfg: &Tcfg| -> Tb { (self.run(cfg))(ma.run(cfg)) } }
+    }
+
+    // monad
+    fn bind<Mb: RunReader<Tcfg>>(
+        self,
+        k_ab: impl Fn(Self::Value) -> Mb
+    ) -> Reader<impl Fn(&Tcfg) -> Mb::Value> {
+        Reader { run_reader: move |cfg: &Tcfg| -> Mb::Value { k_ab(self.run(cfg)).run(cfg) } }
+    }
+
+    fn boxed(self) -> DynReader<Tcfg, Self::Value> where Self: 'static {
+        DynReader { run_reader: Rc::new(move |cfg: &Tcfg| self.run(cfg)) }
+    }
+}
+
+impl<Tcfg, Ta, F: Fn(&Tcfg) -> Ta> RunReader<Tcfg> for Reader<F> {
+    type Value = Ta;
+    #[inline]
+    fn run(&self, cfg: &Tcfg) -> Ta { (self.run_reader)(cfg) }
+}
+
+impl<Tcfg, Ta> RunReader<Tcfg> for DynReader<Tcfg, Ta> {
+    type Value = Ta;
+    fn run(&self, cfg: &Tcfg) -> Ta { (self.run_reader)(cfg) }
+}
+
+
+pub fn reader_unit<Tcfg, Ta: Clone>(a: Ta) -> Reader<impl Fn(&Tcfg) -> Ta + Clone> {
+    Reader { run_reader: move |_: &Tcfg| -> Ta { a.clone() } }
+}
+
+
+// extracts a copy of the configuration from the monadic context to be used.
+pub fn load<Tcfg: Clone>() -> Reader<impl Fn(&Tcfg) -> Tcfg + Copy> {
+    Reader { run_reader: |cfg: &Tcfg| -> Tcfg { cfg.clone() } }
+}
+
+
+// extracts only what is needed from the configuration, without copying the rest.
+pub fn asks<Tcfg, Ta, F: Fn(&Tcfg) -> Ta>(f: F) -> Reader<F> {
+    Reader { run_reader: f }
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::string::{String, ToString};
+    use crate::monads::observe::observe_eq;
+    use crate::monads::prop::Config;
+    use crate::monads::reader::{ReaderMonad, ReaderKleisli, reader_bind};
+
+    #[test]
+    fn test_fmap() {
+        let r0 = load::<&str>().fmap(|cfg| if cfg == "one" { 10 } else { 1 }).fmap(|x| 3 * x);
+        assert_eq!(r0.run(&"one"), 30);
+        assert_eq!(r0.run(&"none"), 3);
+    }
+
+    #[test]
+    fn test_apply() {
+        let r0 = load::<&str>().fmap(|cfg| move |x: i64| if cfg == "one" { x } else { 2 * x }).apply(reader_unit(10));
+        assert_eq!(r0.run(&"one"), 10);
+        assert_eq!(r0.run(&"two"), 20);
+    }
+
+    #[test]
+    fn test_bind() {
+        struct User {
+            uname: String,
+            host: String,
+        }
+        let login = asks(|u: &User| u.uname.clone()).bind(|uname| asks(move |v: &User| format!("{}@{}", uname, v.host)));
+        assert_eq!(login.run(&User { uname: "a".to_string(), host: "b".to_string() }), "a@b".to_string());
+    }
+
+    #[test]
+    fn test_boxed() {
+        let by_hand: ReaderMonad<i64, i64> = reader_bind(
+            ReaderMonad { run_reader: Rc::new(|cfg: &i64| cfg.wrapping_add(1)) },
+            ReaderKleisli { kleisli: Rc::new(|x: i64| ReaderMonad { run_reader: Rc::new(move |cfg: &i64| x.wrapping_mul(*cfg)) }) }
+        );
+        let inline: DynReader<i64, i64> = load::<i64>()
+            .fmap(|cfg| cfg.wrapping_add(1))
+            .bind(|x| load::<i64>().fmap(move |cfg| x.wrapping_mul(cfg)))
+            .boxed();
+        let inline: ReaderMonad<i64, i64> = ReaderMonad { run_reader: inline.run_reader };
+        assert_eq!(observe_eq(&Config::default(), &by_hand, &inline), Ok(()));
+    }
+}
@@ -0,0 +1,161 @@
+use alloc::rc::Rc;
+
+
+// A state computation s -> (a, s) whose closure type is known statically.
+#[derive(Clone, Copy)]
+pub struct State<F> {
+    pub run_state: F
+}
+
+
+// A state computation behind an `Rc<dyn Fn>`, for when the type has to be erased.
+pub struct DynState<Ts, Ta> {
+    pub run_state: Rc<dyn Fn(Ts) -> (Ta, Ts)>
+}
+
+impl<Ts, Ta> Clone for DynState<Ts, Ta> {
+    fn clone(&self) -> Self { DynState { run_state: self.run_state.clone() } }
+}
+
+
+// Anything that can be run as a state computation. The combinators are
+// provided methods, so `State` and `DynState` chain in exactly the same way.
+pub trait RunState<Ts>: Sized {
+    type Value;
+
+    fn run(&self, s: Ts) -> (Self::Value, Ts);
+
+    // functor
+    fn fmap<Tb>(
+        self,
+        f_ab: impl Fn(Self::Value) -> Tb
+    ) -> State<impl Fn(Ts) -> (Tb, Ts)> {
+        State { run_state: move |s: Ts| -> (Tb, Ts) {
+            let a_s: (Self::Value, Ts) = self.run(s);
+            (f_ab(a_s.0), a_s.1)
+        } }
+    }
+
+    // applicative
+    fn apply<Ma: RunState<Ts>, Tb>(
+        self,
+        ma: Ma
+    ) -> State<impl Fn(Ts) -> (Tb, Ts)>
+    where
+        Self::Value: FnOnce(Ma::Value) -> Tb
+    {
+        State { run_state: move |s: Ts| -> (Tb, Ts) {
+            let f_s: (Self::Value, Ts) = self.run(s);
+            let a_s: (Ma::Value, Ts) = ma.run(f_s.1);
+            ((f_s.0)(a_s.0), a_s.1)
+        } }
+    }
+
+    // monad
+    fn bind<Mb: RunState<Ts>>(
+        self,
+        k_ab: impl Fn(Self::Value) -> Mb
+    ) -> State<impl Fn(Ts) -> (Mb::Value, Ts)> {
+        State { run_state: move |s: Ts| -> (Mb::Value, Ts) {
+            let a_s: (Self::Value, Ts) = self.run(s);
+            k_ab(a_s.0).run(a_s.1)
+        } }
+    }
+
+    // runs `self` for its effect on the state only, then `mb`.
+    fn then<Mb: RunState<Ts>>(self, mb: Mb) -> State<impl Fn(Ts) -> (Mb::Value, Ts)> {
+        State { run_state: move |s: Ts| -> (Mb::Value, Ts) { mb.run(self.run(s).1) } }
+    }
+
+    fn boxed(self) -> DynState<Ts, Self::Value> where Self: 'static {
+        DynState { run_state: Rc::new(move |s: Ts| self.run(s)) }
+    }
+}
+
+impl<Ts, Ta, F: Fn(Ts) -> (Ta, Ts)> RunState<Ts> for State<F> {
+    type Value = Ta;
+    #[inline]
+    fn run(&self, s: Ts) -> (Ta, Ts) { (self.run_state)(s) }
+}
+
+impl<Ts, Ta> RunState<Ts> for DynState<Ts, Ta> {
+    type Value = Ta;
+    fn run(&self, s: Ts) -> (Ta, Ts) { (self.run_state)(s) }
+}
+
+
+pub fn state_unit<Ts, Ta: Clone>(a: Ta) -> State<impl Fn(Ts) -> (Ta, Ts) + Clone> {
+    State { run_state: move |s: Ts| -> (Ta, Ts) { (a.clone(), s) } }
+}
+
+
+// extracts the state from the monadic context.
+pub fn get<Ts: Clone>() -> State<impl Fn(Ts) -> (Ts, Ts) + Copy> {
+    State { run_state: |s: Ts| -> (Ts, Ts) { (s.clone(), s) } }
+}
+
+
+// returns the state into the monadic context
+pub fn put<Ts: Clone>(s: Ts) -> State<impl Fn(Ts) -> ((), Ts) + Clone> {
+    State { run_state: move |_: Ts| -> ((), Ts) { ((), s.clone()) } }
+}
+
+
+// applies a function to the state in place, without copying it out.
+pub fn modify<Ts>(f: impl Fn(Ts) -> Ts) -> State<impl Fn(Ts) -> ((), Ts)> {
+    State { run_state: move |s: Ts| -> ((), Ts) { ((), f(s)) } }
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use crate::monads::observe::observe_eq;
+    use crate::monads::prop::Config;
+    use crate::monads::state::{StateMonad, StateKleisli, state_bind};
+
+    #[test]
+    fn test_fmap() {
+        let s0 = get::<i64>().fmap(|x| x + 1);
+        assert_eq!(s0.run(10), (11, 10));
+    }
+
+    #[test]
+    fn test_apply() {
+        let s0 = state_unit(|x: i64| x + 2).apply(get());
+        assert_eq!(s0.run(10), (12, 10));
+    }
+
+    #[test]
+    fn test_bind() {
+        let s0 = get::<(i64, i64)>().bind(|st| put((st.0 + 1, st.1))).then(state_unit(0));
+        assert_eq!(s0.run((10, 13)), (0, (11, 13)));
+
+        let push = |x: i64| modify(move |mut xs: Vec<i64>| { xs.push(x); xs });
+        let s1 = push(1).then(push(2)).then(get()).fmap(|xs| xs.len());
+        assert_eq!(s1.run(vec![0]), (3, vec![0, 1, 2]));
+    }
+
+    #[test]
+    fn test_boxed() {
+        // the type of a recursive computation depends on its depth, so it is boxed.
+        fn count_down(n: i64) -> DynState<i64, i64> {
+            if n == 0 { return state_unit(0).boxed() }
+            modify(|s: i64| s + 1).then(count_down(n - 1)).fmap(move |x| x + n).boxed()
+        }
+        assert_eq!(count_down(4).run(0), (10, 4));
+
+        let by_hand: StateMonad<i64, i64> = state_bind(
+            StateMonad { run_state: Rc::new(|s: i64| (s, s)) },
+            StateKleisli { kleisli: Rc::new(|x: i64| StateMonad { run_state: Rc::new(move |s: i64| (x.wrapping_mul(2), s.wrapping_sub(1))) }) }
+        );
+        let inline: DynState<i64, i64> = get::<i64>()
+            .bind(|x| modify(|s: i64| s.wrapping_sub(1)).then(state_unit(x.wrapping_mul(2))))
+            .boxed();
+        let inline: StateMonad<i64, i64> = StateMonad { run_state: inline.run_state };
+        assert_eq!(observe_eq(&Config::default(), &by_hand, &inline), Ok(()));
+    }
+}
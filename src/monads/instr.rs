@@ -0,0 +1,38 @@
+use core::any::Any;
+use alloc::boxed::Box;
+
+
+// Typed instructions, for the operational monad and the free applicative.
+// An instruction type says what it is answered with. a program over several
+// of them takes an instruction set, an enum with a variant per instruction
+// type that each converts `Into`, and a handler matches on the set and
+// answers every instruction with `Instr::answer`, which only takes a value
+// of that instruction's `Output`. the answers are kept in `Answer`s, whose
+// types are erased so that one handler can give all of them; that also
+// means nothing stops a handler from answering an instruction with
+// another's `answer`, and a program answered so panics when it runs.
+pub trait Instr {
+    type Output: 'static;
+
+    fn answer(&self, a: Self::Output) -> Answer {
+        Answer::new(a)
+    }
+}
+
+
+// the answer to an instruction, as the handler gives it. there is no way to
+// make one other than `Instr::answer`, or to look inside it.
+pub struct Answer(Box<dyn Any>);
+
+impl Answer {
+    pub(crate) fn new<Ta: 'static>(a: Ta) -> Answer {
+        Answer(Box::new(a))
+    }
+
+    // the type is fixed by the instruction the answer is for, so that a
+    // mismatch means an answer made with another instruction's `answer`. it
+    // is only found here, at run time.
+    pub(crate) fn take<Ta: 'static>(self) -> Ta {
+        *self.0.downcast::<Ta>().unwrap_or_else(|_| panic!("instr: an instruction was answered for another"))
+    }
+}
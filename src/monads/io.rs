@@ -0,0 +1,392 @@
+#[cfg(any(test, feature = "testing"))]
+use core::fmt::Debug;
+use alloc::rc::Rc;
+
+#[cfg(any(test, feature = "testing"))]
+use super::observe::ObserveEq;
+#[cfg(feature = "state")]
+use super::state::{StateT, StateMonad};
+#[cfg(feature = "reader")]
+use super::reader::{ReaderT, ReaderMonad};
+#[cfg(feature = "writer")]
+use super::monoid::Monoid;
+#[cfg(feature = "writer")]
+use super::writer::{Writer, writer_unit};
+
+
+// Side effects as values.
+// an `IO` is a description of a program: building one, binding it or
+// passing it around does nothing, and its effects only happen, in bind
+// order, when it is given to `unsafe_run`. it can be run any number of
+// times, doing its effects again each time.
+pub struct IO<Ta> {
+    pub run_io: Rc<dyn Fn() -> Ta>
+}
+
+impl<Ta> Clone for IO<Ta> {
+    fn clone(&self) -> Self { IO { run_io: self.run_io.clone() } }
+}
+
+
+// running is the only way to see what an `IO` gives.
+#[cfg(any(test, feature = "testing"))]
+impl<Ta: PartialEq + Debug> ObserveEq for IO<Ta> {
+    type Input = ();
+    type Output = Ta;
+    fn observe(&self, _: &()) -> Self::Output { unsafe_run(self) }
+}
+
+
+// does the effects of `ma`, and gives its result. the name is Haskell's
+// `unsafePerformIO`: nothing here is memory-unsafe, but from this point on
+// the effects are no longer values.
+pub fn unsafe_run<Ta>(ma: &IO<Ta>) -> Ta {
+    (ma.run_io)()
+}
+
+// an effect, to be done when the `IO` is run rather than now.
+pub fn delay<Ta>(f: impl Fn() -> Ta + 'static) -> IO<Ta> {
+    IO { run_io: Rc::new(f) }
+}
+
+pub fn io_unit<Ta: Clone + 'static>(a: Ta) -> IO<Ta> {
+    IO { run_io: Rc::new(move || -> Ta { a.clone() }) }
+}
+
+pub fn io_fmap<Ta: 'static, Tb: 'static>(f_ab: Rc<dyn Fn(Ta) -> Tb>, ma: IO<Ta>) -> IO<Tb> {
+    IO { run_io: Rc::new(move || -> Tb { f_ab((ma.run_io)()) }) }
+}
+
+pub fn io_apply<Ta: 'static, Tb: 'static>(mf: IO<Rc<dyn Fn(Ta) -> Tb>>, ma: IO<Ta>) -> IO<Tb> {
+    IO { run_io: Rc::new(move || -> Tb {
+        let f_ab: Rc<dyn Fn(Ta) -> Tb> = (mf.run_io)();
+        f_ab((ma.run_io)())
+    }) }
+}
+
+pub struct IOKleisli<Ta, Tb> {
+    pub kleisli: Rc<dyn Fn(Ta) -> IO<Tb>>
+}
+
+impl<Ta, Tb> Clone for IOKleisli<Ta, Tb> {
+    fn clone(&self) -> Self { IOKleisli { kleisli: self.kleisli.clone() } }
+}
+
+pub fn io_bind<Ta: 'static, Tb: 'static>(ma: IO<Ta>, k_ab: IOKleisli<Ta, Tb>) -> IO<Tb> {
+    IO { run_io: Rc::new(move || -> Tb {
+        let mb: IO<Tb> = (k_ab.kleisli)((ma.run_io)());
+        (mb.run_io)()
+    }) }
+}
+
+
+// bracket :: IO r -> (r -> IO a) -> (r -> IO ()) -> IO a
+// acquires a resource, uses it, and releases it. `release` runs once
+// `using` is done, however it ends: with a value, with an `Err` in it, or
+// with a panic, in which case the panic carries on once the resource is
+// released. a panic in `acquire` leaves nothing to release. brackets inside
+// `using` release theirs first, as nested scopes would.
+pub fn bracket<Tr: Clone + 'static, Ta: 'static>(
+    acquire: IO<Tr>,
+    using: IOKleisli<Tr, Ta>,
+    release: IOKleisli<Tr, ()>
+) -> IO<Ta> {
+    IO { run_io: Rc::new(move || -> Ta {
+        let resource: Tr = unsafe_run(&acquire);
+        let guard: Release<Tr> = Release { resource: Some(resource.clone()), release: release.clone() };
+        let a: Ta = unsafe_run(&(using.kleisli)(resource));
+        drop(guard);
+        a
+    }) }
+}
+
+// releases the resource when dropped, so that unwinding does it too.
+struct Release<Tr> {
+    resource: Option<Tr>,
+    release: IOKleisli<Tr, ()>
+}
+
+impl<Tr> Drop for Release<Tr> {
+    fn drop(&mut self) {
+        if let Some(resource) = self.resource.take() {
+            unsafe_run(&(self.release.kleisli)(resource))
+        }
+    }
+}
+
+
+// liftIO :: IO a -> m a
+// the state and reader monads are already deferred, a function of their
+// state or configuration, so an `IO` in them runs when they are run, in
+// bind order with the rest. the plain writer is a finished value, so the
+// `IO` is lifted into `WriterIO`, the writer over `IO`, instead.
+pub trait LiftIO<Ta> {
+    fn lift_io(io: IO<Ta>) -> Self;
+}
+
+pub fn lift_io<M: LiftIO<Ta>, Ta>(io: IO<Ta>) -> M {
+    M::lift_io(io)
+}
+
+#[cfg(feature = "state")]
+impl<Ts: 'static, Ta: 'static> LiftIO<Ta> for StateMonad<Ts, Ta> {
+    fn lift_io(io: IO<Ta>) -> Self {
+        StateT { run_state: Rc::new(move |s: Ts| -> (Ta, Ts) { (unsafe_run(&io), s) }) }
+    }
+}
+
+#[cfg(feature = "reader")]
+impl<Tcfg: 'static, Ta: 'static> LiftIO<Ta> for ReaderMonad<Tcfg, Ta> {
+    fn lift_io(io: IO<Ta>) -> Self {
+        ReaderT { run_reader: Rc::new(move |_: &Tcfg| -> Ta { unsafe_run(&io) }) }
+    }
+}
+
+
+// WriterT w IO a: effects that also write a log. nothing is written until
+// it is run, and what is run twice is written twice.
+#[cfg(feature = "writer")]
+pub type WriterIO<Ta, Tlog> = IO<Writer<Ta, Tlog>>;
+
+#[cfg(feature = "writer")]
+impl<Ta: 'static, Tlog: Monoid<T = Tlog> + 'static> LiftIO<Ta> for WriterIO<Ta, Tlog> {
+    fn lift_io(io: IO<Ta>) -> Self {
+        io_fmap(Rc::new(writer_unit), io)
+    }
+}
+
+#[cfg(feature = "writer")]
+pub fn writer_io_unit<Ta: Clone + 'static, Tlog: Monoid<T = Tlog> + 'static>(a: Ta) -> WriterIO<Ta, Tlog> {
+    IO { run_io: Rc::new(move || -> Writer<Ta, Tlog> { writer_unit(a.clone()) }) }
+}
+
+#[cfg(feature = "writer")]
+pub fn writer_io_bind<Ta: 'static, Tb: 'static, Tlog: Monoid<T = Tlog> + 'static>(
+    ma: WriterIO<Ta, Tlog>,
+    k_ab: IOKleisli<Ta, Writer<Tb, Tlog>>
+) -> WriterIO<Tb, Tlog> {
+    IO { run_io: Rc::new(move || -> Writer<Tb, Tlog> {
+        let a_log: Writer<Ta, Tlog> = unsafe_run(&ma);
+        let b_log: Writer<Tb, Tlog> = unsafe_run(&(k_ab.kleisli)(a_log.value));
+        let mut log: Tlog = a_log.log;
+        log.mappend_assign(b_log.log);
+        Writer { value: b_log.value, log }
+    }) }
+}
+
+// tell :: w -> WriterT w IO ()
+#[cfg(feature = "writer")]
+pub fn writer_io_log<Tlog: Clone + 'static>(msg: Tlog) -> WriterIO<(), Tlog> {
+    IO { run_io: Rc::new(move || -> Writer<(), Tlog> { Writer { value: (), log: msg.clone() } }) }
+}
+
+
+// do-notation. `io_do!(writer => ...)` builds a `WriterIO` instead, its
+// binds joining the logs. internally every rule carries the module, the
+// bind and the kleisli arrow of the target.
+#[macro_export]
+macro_rules! io_do {
+    (writer => $($rest:tt)*) => { $crate::io_do!(@[$crate::monads::io][writer_io_bind][IOKleisli] $($rest)*) };
+
+    // trailing comma
+    (@[$($m:tt)*][$bind:ident][$k:ident]) => {};
+
+    (@[$($m:tt)*][$bind:ident][$k:ident] $v:ident = $e:expr,  $($rest:tt)*) => { (|$v| { $crate::io_do!(@[$($m)*][$bind][$k] $($rest)*) })($e) };
+
+    (@[$($m:tt)*][$bind:ident][$k:ident] $v:ident <- $e:expr, $($rest:tt)*) => {
+        $($m)*::$bind(
+            $e,
+            $($m)*::$k {
+                kleisli: $crate::prelude::Rc::new( move |$v| { $crate::io_do!(@[$($m)*][$bind][$k] $($rest)*) } )
+            }
+        )
+    };
+
+    (@[$($m:tt)*][$bind:ident][$k:ident] $e:expr, $($rest:tt)*) => {
+        $($m)*::$bind(
+            $e,
+            $($m)*::$k {
+                kleisli: $crate::prelude::Rc::new( move |_| { $crate::io_do!(@[$($m)*][$bind][$k] $($rest)*) } )
+            }
+        )
+    };
+
+    (@[$($m:tt)*][$bind:ident][$k:ident] $e:expr) => { $e };
+
+    ($($rest:tt)*) => { $crate::io_do!(@[$crate::monads::io][io_bind][IOKleisli] $($rest)*) };
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::RefCell;
+    use alloc::format;
+    use alloc::string::{String, ToString};
+    use alloc::boxed::Box;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use std::panic::{AssertUnwindSafe, catch_unwind};
+    use crate::monads::laws::{check_functor_laws, check_monad_laws};
+    use crate::monads::prop::Config;
+
+    // leaked, so that the steps of a do block can all share it without cloning.
+    type Console = &'static RefCell<Vec<String>>;
+
+    fn console() -> Console {
+        Box::leak(Box::new(RefCell::new(Vec::new())))
+    }
+
+    fn print(console: Console, line: &str) -> IO<()> {
+        let line: String = line.to_string();
+        delay(move || console.borrow_mut().push(line.clone()))
+    }
+
+    fn tick(counter: &'static RefCell<i64>) -> IO<i64> {
+        delay(move || { *counter.borrow_mut() += 1; *counter.borrow() })
+    }
+
+    fn lines(console: Console) -> Vec<String> {
+        console.borrow_mut().drain(..).collect()
+    }
+
+    #[test]
+    fn test_deferred() {
+        let console: Console = console();
+        let counter: &'static RefCell<i64> = Box::leak(Box::new(RefCell::new(0)));
+
+        let program: IO<i64> = io_do!(
+            print(console, "start"),
+            a <- tick(counter),
+            b <- tick(counter),
+            print(console, &format!("{} {}", a, b)),
+            io_unit(a + b)
+        );
+        // nothing has happened yet.
+        assert!(console.borrow().is_empty());
+        assert_eq!(*counter.borrow(), 0);
+
+        assert_eq!(unsafe_run(&program), 3);
+        assert_eq!(lines(console), vec!["start", "1 2"]);
+        // running again does it all again.
+        assert_eq!(unsafe_run(&program), 7);
+        assert_eq!(lines(console), vec!["start", "3 4"]);
+    }
+
+    // a resource that logs its life.
+    fn open(console: Console, name: &'static str) -> IO<&'static str> {
+        io_bind(print(console, &format!("open {}", name)), IOKleisli { kleisli: Rc::new(move |_| io_unit(name)) })
+    }
+
+    fn close(console: Console) -> IOKleisli<&'static str, ()> {
+        IOKleisli { kleisli: Rc::new(move |name: &'static str| print(console, &format!("close {}", name))) }
+    }
+
+    #[test]
+    fn test_bracket() {
+        let console: Console = console();
+        let ok: IO<usize> = bracket(
+            open(console, "a"),
+            IOKleisli { kleisli: Rc::new(move |name: &'static str| io_do!(
+                print(console, &format!("use {}", name)),
+                io_unit(name.len())
+            )) },
+            close(console)
+        );
+        assert!(console.borrow().is_empty());
+        assert_eq!(unsafe_run(&ok), 1);
+        assert_eq!(lines(console), vec!["open a", "use a", "close a"]);
+
+        // an inner bracket releases first.
+        let nested: IO<()> = bracket(
+            open(console, "a"),
+            IOKleisli { kleisli: Rc::new(move |_| bracket(open(console, "b"), IOKleisli { kleisli: Rc::new(|_| io_unit(())) }, close(console))) },
+            close(console)
+        );
+        unsafe_run(&nested);
+        assert_eq!(lines(console), vec!["open a", "open b", "close b", "close a"]);
+
+        // an `Err` is a result like any other.
+        let failed: IO<Result<(), String>> = bracket(
+            open(console, "a"),
+            IOKleisli { kleisli: Rc::new(|_| io_unit(Err("no space".to_string()))) },
+            close(console)
+        );
+        assert_eq!(unsafe_run(&failed), Err("no space".to_string()));
+        assert_eq!(lines(console), vec!["open a", "close a"]);
+    }
+
+    #[test]
+    fn test_bracket_panic() {
+        let console: Console = console();
+        let panicking: IO<()> = bracket(
+            open(console, "a"),
+            IOKleisli { kleisli: Rc::new(|_| delay(|| panic!("in use"))) },
+            close(console)
+        );
+        assert!(catch_unwind(AssertUnwindSafe(|| unsafe_run(&panicking))).is_err());
+        assert_eq!(lines(console), vec!["open a", "close a"]);
+    }
+
+    #[cfg(feature = "state")]
+    #[test]
+    fn test_lift_io_state() {
+        use crate::monads::state::{get, put};
+        use crate::state_do;
+
+        let console: Console = console();
+        let counter: StateMonad<i64, ()> = state_do!(
+            n <- get(),
+            lift_io(print(console, &format!("at {}", n))),
+            put(n + 1)
+        );
+        assert!(console.borrow().is_empty());
+        assert_eq!((counter.run_state)(4), ((), 5));
+        assert_eq!(lines(console), vec!["at 4"]);
+    }
+
+    #[cfg(feature = "reader")]
+    #[test]
+    fn test_lift_io_reader() {
+        use crate::monads::reader::{asks, reader_unit, run_reader};
+        use crate::reader_do;
+
+        let console: Console = console();
+        let greet: ReaderMonad<String, usize> = reader_do!(
+            name <- asks(|name: &String| name.clone()),
+            lift_io(print(console, &format!("hello {}", name))),
+            reader_unit(name.len())
+        );
+        assert_eq!(run_reader(&greet, "io".to_string()), 2);
+        assert_eq!(lines(console), vec!["hello io"]);
+    }
+
+    #[cfg(feature = "writer")]
+    #[test]
+    fn test_lift_io_writer() {
+        let console: Console = console();
+        let logged: WriterIO<i64, Vec<String>> = io_do!(writer =>
+            writer_io_log(vec!["start".to_string()]),
+            lift_io(print(console, "working")),
+            x <- lift_io(io_unit(20)),
+            writer_io_log(vec![format!("got {}", x)]),
+            writer_io_unit(x + 1)
+        );
+        assert!(console.borrow().is_empty());
+        assert_eq!(unsafe_run(&logged).run_writer(), (21, vec!["start".to_string(), "got 20".to_string()]));
+        assert_eq!(lines(console), vec!["working"]);
+    }
+
+    #[test]
+    fn test_laws() {
+        let gen = |p: &(i64, i64)| -> IO<i64> { io_fmap(Rc::new(|x: i64| x.wrapping_mul(3)), io_unit(p.0 ^ p.1)) };
+        check_functor_laws(&Config::default(), gen, io_fmap).unwrap();
+        check_monad_laws(
+            &Config::default(),
+            gen,
+            io_unit,
+            |m, k| io_bind(m, IOKleisli { kleisli: k })
+        ).unwrap();
+    }
+}
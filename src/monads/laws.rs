@@ -0,0 +1,148 @@
+use core::hash::Hash;
+use alloc::rc::Rc;
+
+use super::monoid::Monoid;
+use super::observe::{ObserveEq, observe_on};
+use super::prop::{Arbitrary, Config, Counterexample, Fun, expect_eq, forall};
+
+
+// Reusable law checks for the structures in this crate.
+//
+// Rust has no higher kinded types, so a monad is handed over as plain functions:
+// `gen` builds a monadic value out of generated data `p` and `unit`/`fmap`/`bind`
+// are the operations under test. Both sides of a law are compared through
+// `ObserveEq` on a generated input, which is shrunk along with everything else.
+// Kleisli arrows a -> m a are generated as arbitrary functions a -> p
+// followed by `gen`.
+
+
+// a function a -> b, as it is passed to `fmap` and `bind`.
+pub type Arrow<A, B> = Rc<dyn Fn(A) -> B>;
+
+
+// mempty <> m        == m
+// m <> mempty        == m
+// (a <> b) <> c      == a <> (b <> c)
+pub fn check_monoid_laws<M: Monoid<T = M> + Arbitrary + PartialEq>(
+    cfg: &Config
+) -> Result<(), Counterexample> {
+    forall(cfg, "monoid left identity", |m: &M| {
+        expect_eq(M::mempty().mappend(m.clone()), m.clone())
+    })?;
+    forall(cfg, "monoid right identity", |m: &M| {
+        expect_eq(m.mappend(M::mempty()), m.clone())
+    })?;
+    forall(cfg, "monoid associativity", |(a, b, c): &(M, M, M)| {
+        expect_eq(a.mappend(b.clone()).mappend(c.clone()), a.mappend(b.mappend(c.clone())))
+    })?;
+    forall(cfg, "mappend_assign agrees with mappend", |(a, b): &(M, M)| {
+        let mut lhs: M = a.clone();
+        lhs.mappend_assign(b.clone());
+        expect_eq(lhs, a.mappend(b.clone()))
+    })
+}
+
+
+// fmap id          == id
+// fmap (g . f)     == fmap g . fmap f
+pub fn check_functor_laws<P, A, M>(
+    cfg: &Config,
+    gen: fn(&P) -> M,
+    fmap: fn(Arrow<A, A>, M) -> M
+) -> Result<(), Counterexample>
+where
+    P: Arbitrary,
+    A: Arbitrary + Hash + 'static,
+    M: ObserveEq,
+{
+    forall(cfg, "functor identity", |(p, i): &(P, M::Input)| {
+        observe_on(&fmap(Rc::new(|a| a), gen(p)), &gen(p), i)
+    })?;
+    forall(cfg, "functor composition", |(p, f, g, i): &(P, Fun<A, A>, Fun<A, A>, M::Input)| {
+        let (f0, g0): (Fun<A, A>, Fun<A, A>) = (f.clone(), g.clone());
+        let g_f: Arrow<A, A> = Rc::new(move |a: A| -> A { g0.apply(&f0.apply(&a)) });
+        observe_on(&fmap(g_f, gen(p)), &fmap(g.to_rc(), fmap(f.to_rc(), gen(p))), i)
+    })
+}
+
+
+// unit a >>= k           == k a
+// m >>= unit             == m
+// (m >>= f) >>= g        == m >>= (\a -> f a >>= g)
+pub fn check_monad_laws<P, A, M>(
+    cfg: &Config,
+    gen: fn(&P) -> M,
+    unit: fn(A) -> M,
+    bind: fn(M, Arrow<A, M>) -> M
+) -> Result<(), Counterexample>
+where
+    P: Arbitrary + 'static,
+    A: Arbitrary + Hash + 'static,
+    M: ObserveEq + 'static,
+{
+    let kleisli = |k: &Fun<A, P>| -> Arrow<A, M> {
+        let k: Fun<A, P> = k.clone();
+        Rc::new(move |a: A| -> M { gen(&k.apply(&a)) })
+    };
+
+    forall(cfg, "monad left identity", |(a, k, i): &(A, Fun<A, P>, M::Input)| {
+        observe_on(&bind(unit(a.clone()), kleisli(k)), &kleisli(k)(a.clone()), i)
+    })?;
+    forall(cfg, "monad right identity", |(p, i): &(P, M::Input)| {
+        observe_on(&bind(gen(p), Rc::new(unit)), &gen(p), i)
+    })?;
+    forall(cfg, "monad associativity", |(p, f, g, i): &(P, Fun<A, P>, Fun<A, P>, M::Input)| {
+        let (f0, g0): (Arrow<A, M>, Arrow<A, M>) = (kleisli(f), kleisli(g));
+        let f_then_g: Arrow<A, M> = Rc::new(move |a: A| -> M { bind(f0(a), g0.clone()) });
+        observe_on(&bind(bind(gen(p), kleisli(f)), kleisli(g)), &bind(gen(p), f_then_g), i)
+    })
+}
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::string::{String, ToString};
+    use alloc::vec::Vec;
+    use crate::monads::prop::Rng;
+
+    // the newline join without special casing the empty string.
+    #[derive(Debug, Clone, PartialEq)]
+    struct Lines(String);
+
+    impl Monoid for Lines {
+        type T = Lines;
+        fn mempty() -> Self::T { Lines("".to_string()) }
+        fn mappend(&self, other: Self::T) -> Self::T { Lines(format!("{}\n{}", self.0, other.0)) }
+    }
+
+    impl Arbitrary for Lines {
+        fn arbitrary(rng: &mut Rng, size: usize) -> Self { Lines(String::arbitrary(rng, size)) }
+        fn shrink(&self) -> Vec<Self> { self.0.shrink().into_iter().map(Lines).collect() }
+    }
+
+    #[test]
+    fn test_broken_monoid() {
+        let failure = check_monoid_laws::<Lines>(&Config::default()).unwrap_err();
+        assert_eq!(failure.property, "monoid left identity");
+        assert_eq!(failure.input, "Lines(\"\")");
+    }
+
+    #[test]
+    #[cfg(feature = "writer")]
+    fn test_broken_monad() {
+        use alloc::vec;
+        use crate::monads::writer::Writer;
+
+        // a writer whose bind forgets the log of the first computation.
+        let failure = check_monad_laws(
+            &Config::default(),
+            |p: &(i64, Vec<i64>)| Writer::from(p.clone()),
+            |a| Writer::new(a, vec![]),
+            |m, k| k(m.value)
+        ).unwrap_err();
+        assert_eq!(failure.property, "monad right identity");
+        assert_eq!(failure.input, "((0, [0]), ())");
+    }
+}
@@ -0,0 +1,8 @@
+// Lazy variants of the monads whose results are built eagerly.
+// Instead of the finished value, they hold the recipe for it as a list of
+// thunks which only run when the result is asked for, so work whose result
+// is never looked at (a log nobody reads) is never done.
+// The do-notation builds them when given a leading `lazy =>`.
+
+#[cfg(feature = "writer")]
+pub mod writer;
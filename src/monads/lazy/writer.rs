@@ -0,0 +1,187 @@
+#[cfg(any(test, feature = "testing"))]
+use core::fmt::Debug;
+use alloc::rc::Rc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::monads::monoid::Monoid;
+#[cfg(any(test, feature = "testing"))]
+use crate::monads::observe::ObserveEq;
+use crate::monads::writer;
+
+
+// A value together with the pieces its log will be made of.
+// Each piece is a thunk, and binds only append thunks to the list: the log
+// is materialised, in order, by `run_writer`/`exec_writer`. a log that is
+// never looked at never gets built, and neither does any message in it.
+pub struct Writer<Ta, Tlog> {
+    pub value: Ta,
+    entries: Vec<Rc<dyn Fn() -> Tlog>>
+}
+
+pub type WriterMonad<Ta, Tlog> = Writer<Ta, Tlog>;
+
+
+impl<Ta, Tlog: Monoid<T = Tlog>> Writer<Ta, Tlog> {
+    // runWriter :: Writer w a -> (a, w)
+    pub fn run_writer(self) -> writer::Writer<Ta, Tlog> {
+        let log: Tlog = self.exec_writer();
+        writer::Writer { value: self.value, log }
+    }
+
+    // builds the log, leaving the writer as it is.
+    pub fn exec_writer(&self) -> Tlog {
+        let mut log: Tlog = Tlog::mempty();
+        for entry in self.entries.iter() {
+            log.mappend_assign(entry());
+        }
+        log
+    }
+}
+
+impl<Ta, Tlog> Writer<Ta, Tlog> {
+    // the value alone; the log is dropped without ever being built.
+    pub fn eval_writer(self) -> Ta { self.value }
+}
+
+impl<Ta: Clone, Tlog> Clone for Writer<Ta, Tlog> {
+    fn clone(&self) -> Self { Writer { value: self.value.clone(), entries: self.entries.clone() } }
+}
+
+
+// observing a lazy writer is what forces its log.
+#[cfg(any(test, feature = "testing"))]
+impl<Ta, Tlog> ObserveEq for Writer<Ta, Tlog>
+where
+    Ta: PartialEq + Debug + Clone,
+    Tlog: Monoid<T = Tlog> + PartialEq + Debug + Clone
+{
+    type Input = ();
+    type Output = writer::Writer<Ta, Tlog>;
+    fn observe(&self, _: &()) -> Self::Output { writer::Writer { value: self.value.clone(), log: self.exec_writer() } }
+}
+
+
+pub fn writer_unit<Ta, Tlog>(a: Ta) -> WriterMonad<Ta, Tlog> {
+    Writer { value: a, entries: Vec::new() }
+}
+
+
+// functor
+pub fn writer_fmap<Ta, Tb, Tlog>(
+    f_ab: impl FnOnce(Ta) -> Tb,
+    ma: WriterMonad<Ta, Tlog>
+) -> WriterMonad<Tb, Tlog> {
+    Writer { value: f_ab(ma.value), entries: ma.entries }
+}
+
+
+// applicative
+pub fn writer_apply<Ta, Tb, Tlog>(
+    mf: WriterMonad<impl FnOnce(Ta) -> Tb, Tlog>,
+    ma: WriterMonad<Ta, Tlog>
+) -> WriterMonad<Tb, Tlog> {
+    let mut entries: Vec<Rc<dyn Fn() -> Tlog>> = mf.entries;
+    entries.extend(ma.entries);
+    Writer { value: (mf.value)(ma.value), entries }
+}
+
+
+// monad
+pub struct WriterKleisli<Ta, Tb, Tlog> {
+    pub kleisli: Rc<dyn Fn(Ta) -> WriterMonad<Tb, Tlog>>
+}
+
+impl<Ta, Tb, Tlog> Clone for WriterKleisli<Ta, Tb, Tlog> {
+    fn clone(&self) -> Self { WriterKleisli { kleisli: self.kleisli.clone() } }
+}
+
+pub fn writer_bind<Ta, Tb, Tlog>(
+    ma: WriterMonad<Ta, Tlog>,
+    k_ab: WriterKleisli<Ta, Tb, Tlog>
+) -> WriterMonad<Tb, Tlog> {
+    let b_log_ab: WriterMonad<Tb, Tlog> = (k_ab.kleisli)(ma.value);
+    let mut entries: Vec<Rc<dyn Fn() -> Tlog>> = ma.entries;
+    entries.extend(b_log_ab.entries);
+    Writer { value: b_log_ab.value, entries }
+}
+
+
+// pushes a message into the log.
+pub fn log<Tlog: Clone + 'static>(msg: Tlog) -> WriterMonad<(), Tlog> {
+    Writer { value: (), entries: vec![Rc::new(move || -> Tlog { msg.clone() })] }
+}
+
+
+// pushes a message into the log that is only written if the log is read.
+pub fn log_with<Tlog>(msg: impl Fn() -> Tlog + 'static) -> WriterMonad<(), Tlog> {
+    Writer { value: (), entries: vec![Rc::new(msg)] }
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use core::cell::Cell;
+    use alloc::format;
+    use alloc::string::{String, ToString};
+    use crate::monads::laws::{check_functor_laws, check_monad_laws};
+    use crate::monads::prop::Config;
+    use crate::writer_do;
+
+    #[test]
+    fn test_do() {
+        let do_calculation = |x: i64| writer_do!(lazy =>
+            log(format!("received number {}", x)),
+            x0 = x + 10,
+            log_with(move || format!("added 10 to get {}", x0)),
+            writer_unit(x0)
+        );
+        assert_eq!(
+            do_calculation(5).run_writer(),
+            writer::Writer::new(15, "received number 5\nadded 10 to get 15".to_string())
+        );
+    }
+
+    #[test]
+    fn test_apply() {
+        let k: i64 = 7;
+        let w: WriterMonad<i64, String> = writer_apply(
+            writer_fmap(move |_| move |x: i64| k * x, log("hello".to_string())),
+            writer_fmap(|_| 3, log("goodbye".to_string()))
+        );
+        assert_eq!(w.run_writer(), writer::Writer::new(21, "hello\ngoodbye".to_string()));
+    }
+
+    #[test]
+    fn test_lazy() {
+        let forced: Rc<Cell<usize>> = Rc::new(Cell::new(0));
+        let counter: Rc<Cell<usize>> = forced.clone();
+        let w: WriterMonad<i64, String> = writer_do!(lazy =>
+            log_with(move || { counter.set(counter.get() + 1); "expensive".to_string() }),
+            writer_unit(42)
+        );
+
+        assert_eq!(w.clone().eval_writer(), 42);
+        assert_eq!(forced.get(), 0);
+
+        assert_eq!(w.exec_writer(), "expensive".to_string());
+        assert_eq!(forced.get(), 1);
+    }
+
+    #[test]
+    fn test_laws() {
+        fn gen(p: &(i64, String)) -> WriterMonad<i64, String> {
+            let a: i64 = p.0;
+            writer_fmap(move |_| a, log(p.1.clone()))
+        }
+        check_functor_laws(&Config::default(), gen, |f, m| writer_fmap(move |a| f(a), m)).unwrap();
+        check_monad_laws(
+            &Config::default(),
+            gen,
+            writer_unit,
+            |m, k| writer_bind(m, WriterKleisli { kleisli: k })
+        ).unwrap();
+    }
+}
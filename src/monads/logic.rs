@@ -0,0 +1,594 @@
+#[cfg(any(test, feature = "testing"))]
+use core::fmt::Debug;
+#[cfg(feature = "state")]
+use core::marker::PhantomData;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+use super::identity::Identity;
+#[cfg(any(test, feature = "testing"))]
+use super::observe::ObserveEq;
+#[cfg(feature = "state")]
+use super::state::{StateMonad, StateKleisli, state_bind};
+
+
+// The base monad of a `LogicT`.
+// unlike `identity::Monad`, `bind` may keep its continuation, so lazy monads
+// such as `StateMonad` can be bases. `fork` takes a computation to run later
+// and returns one that will run as if it were run now: a base with a state
+// hands it the state as it is at the fork, so that every branch of a search
+// starts from the state at its choice point, whatever the branches before
+// it did to theirs.
+pub trait LogicBase {
+    type M<Ta>;
+
+    fn unit<Ta: Clone + 'static>(a: Ta) -> Self::M<Ta>;
+
+    fn bind<Ta: 'static, Tb: 'static>(ma: Self::M<Ta>, k_ab: Rc<dyn Fn(Ta) -> Self::M<Tb>>) -> Self::M<Tb>;
+
+    fn fork<Ta: 'static>(later: Later<Self, Ta>) -> Self::M<Later<Self, Ta>>;
+
+    // the first `n` results, in the base. by default each result is taken in
+    // the `bind` of the one before, so running what it gives nests a call
+    // per result; a base that can step the search in a loop should do so.
+    fn observe<Ta: Clone + 'static>(n: usize, ma: LogicT<Self, Ta>) -> Self::M<Vec<Ta>> where Self: Sized + 'static {
+        observe_onto(None, n, ma)
+    }
+}
+
+// a base computation, not started yet.
+pub type Later<B, Ta> = Rc<dyn Fn() -> <B as LogicBase>::M<Ta>>;
+
+
+// without a base there is nothing to save at a fork.
+impl LogicBase for Identity {
+    type M<Ta> = Ta;
+
+    fn unit<Ta: Clone + 'static>(a: Ta) -> Ta { a }
+
+    fn bind<Ta: 'static, Tb: 'static>(ma: Ta, k_ab: Rc<dyn Fn(Ta) -> Tb>) -> Tb { k_ab(ma) }
+
+    fn fork<Ta: 'static>(later: Later<Self, Ta>) -> Later<Self, Ta> { later }
+
+    // one result at a time, in a loop.
+    fn observe<Ta: Clone + 'static>(n: usize, ma: Logic<Ta>) -> Vec<Ta> { ma.iter().take(n).collect() }
+}
+
+
+// `StateMonad` as a base: the state is kept per branch.
+#[cfg(feature = "state")]
+pub struct StateBase<Ts>(PhantomData<Ts>);
+
+#[cfg(feature = "state")]
+impl<Ts: Clone + 'static> LogicBase for StateBase<Ts> {
+    type M<Ta> = StateMonad<Ts, Ta>;
+
+    fn unit<Ta: Clone + 'static>(a: Ta) -> StateMonad<Ts, Ta> {
+        StateMonad { run_state: Rc::new(move |s: Ts| -> (Ta, Ts) { (a.clone(), s) }) }
+    }
+
+    fn bind<Ta: 'static, Tb: 'static>(ma: StateMonad<Ts, Ta>, k_ab: Rc<dyn Fn(Ta) -> StateMonad<Ts, Tb>>) -> StateMonad<Ts, Tb> {
+        state_bind(ma, StateKleisli { kleisli: k_ab })
+    }
+
+    fn fork<Ta: 'static>(later: Later<Self, Ta>) -> StateMonad<Ts, Later<Self, Ta>> {
+        StateMonad { run_state: Rc::new(move |s: Ts| -> (Later<Self, Ta>, Ts) {
+            let (later, saved): (Later<Self, Ta>, Ts) = (later.clone(), s.clone());
+            let from_saved: Later<Self, Ta> = Rc::new(move || -> StateMonad<Ts, Ta> {
+                let (later, saved): (Later<Self, Ta>, Ts) = (later.clone(), saved.clone());
+                StateMonad { run_state: Rc::new(move |_: Ts| -> (Ta, Ts) { (later().run_state)(saved.clone()) }) }
+            });
+            (from_saved, s)
+        }) }
+    }
+
+    // one result at a time, in a loop, handing each step the state the one
+    // before left.
+    fn observe<Ta: Clone + 'static>(n: usize, ma: LogicT<Self, Ta>) -> StateMonad<Ts, Vec<Ta>> {
+        StateMonad { run_state: Rc::new(move |mut s: Ts| -> (Vec<Ta>, Ts) {
+            let (mut found, mut rest): (Vec<Ta>, LogicT<Self, Ta>) = (Vec::new(), ma.clone());
+            while found.len() < n {
+                let step: StateMonad<Ts, Split<Self, Ta>> = (rest.run_logic)();
+                match (step.run_state)(s) {
+                    (Split::Empty, next) => return (found, next),
+                    (Split::Cons(a, more), next) => {
+                        found.push(a);
+                        (rest, s) = (more, next);
+                    }
+                }
+            }
+            (found, s)
+        }) }
+    }
+}
+
+
+// LogicT m a: a search with results `a` over the base `B`, as the step
+// that splits off its first result. the step is only taken when asked
+// for, so the search space may be infinite.
+pub struct LogicT<B: LogicBase, Ta> {
+    pub run_logic: Later<B, Split<B, Ta>>
+}
+
+pub enum Split<B: LogicBase, Ta> {
+    Empty,
+    Cons(Ta, LogicT<B, Ta>)
+}
+
+pub type Logic<Ta> = LogicT<Identity, Ta>;
+
+impl<B: LogicBase, Ta> Clone for LogicT<B, Ta> {
+    fn clone(&self) -> Self { LogicT { run_logic: self.run_logic.clone() } }
+}
+
+impl<B: LogicBase, Ta: Clone> Clone for Split<B, Ta> {
+    fn clone(&self) -> Self {
+        match self {
+            Split::Empty => Split::Empty,
+            Split::Cons(a, rest) => Split::Cons(a.clone(), rest.clone())
+        }
+    }
+}
+
+
+// a search without a base is observed by its first results.
+#[cfg(any(test, feature = "testing"))]
+impl<Ta: PartialEq + Debug + Clone + 'static> ObserveEq for Logic<Ta> {
+    type Input = ();
+    type Output = Vec<Ta>;
+    fn observe(&self, _: &()) -> Self::Output { self.iter().take(32).collect() }
+}
+
+
+pub fn logic_unit<B: LogicBase + 'static, Ta: Clone + 'static>(a: Ta) -> LogicT<B, Ta> {
+    LogicT { run_logic: Rc::new(move || -> B::M<Split<B, Ta>> { B::unit(Split::Cons(a.clone(), logic_empty())) }) }
+}
+
+// mzero
+pub fn logic_empty<B: LogicBase + 'static, Ta: Clone + 'static>() -> LogicT<B, Ta> {
+    LogicT { run_logic: Rc::new(|| -> B::M<Split<B, Ta>> { B::unit(Split::Empty) }) }
+}
+
+pub fn logic_guard<B: LogicBase + 'static>(cond: bool) -> LogicT<B, ()> {
+    if cond { logic_unit(()) } else { logic_empty() }
+}
+
+// lift :: m a -> LogicT m a
+// the base computation is a thunk, since the search may run it more than once.
+pub fn logic_lift<B: LogicBase + 'static, Ta: Clone + 'static>(ma: impl Fn() -> B::M<Ta> + 'static) -> LogicT<B, Ta> {
+    LogicT { run_logic: Rc::new(move || -> B::M<Split<B, Ta>> {
+        B::bind(ma(), Rc::new(|a: Ta| -> B::M<Split<B, Ta>> { B::unit(Split::Cons(a, logic_empty())) }))
+    }) }
+}
+
+
+// mplus: the results of `ma`, then those of `mb`.
+pub fn logic_plus<B: LogicBase + 'static, Ta: Clone + 'static>(ma: LogicT<B, Ta>, mb: LogicT<B, Ta>) -> LogicT<B, Ta> {
+    LogicT { run_logic: Rc::new(move || -> B::M<Split<B, Ta>> {
+        let ma: LogicT<B, Ta> = ma.clone();
+        B::bind(B::fork(mb.run_logic.clone()), Rc::new(move |mb: Later<B, Split<B, Ta>>| -> B::M<Split<B, Ta>> {
+            B::bind((ma.run_logic)(), Rc::new(move |r: Split<B, Ta>| -> B::M<Split<B, Ta>> {
+                match r {
+                    Split::Empty => mb(),
+                    Split::Cons(a, rest) => B::unit(Split::Cons(a, logic_plus(rest, LogicT { run_logic: mb.clone() })))
+                }
+            }))
+        }))
+    }) }
+}
+
+// the results of `ma` and `mb`, taking turns, so that an infinite `ma`
+// does not hide the results of `mb`.
+pub fn interleave<B: LogicBase + 'static, Ta: Clone + 'static>(ma: LogicT<B, Ta>, mb: LogicT<B, Ta>) -> LogicT<B, Ta> {
+    LogicT { run_logic: Rc::new(move || -> B::M<Split<B, Ta>> {
+        let ma: LogicT<B, Ta> = ma.clone();
+        B::bind(B::fork(mb.run_logic.clone()), Rc::new(move |mb: Later<B, Split<B, Ta>>| -> B::M<Split<B, Ta>> {
+            B::bind((ma.run_logic)(), Rc::new(move |r: Split<B, Ta>| -> B::M<Split<B, Ta>> {
+                match r {
+                    Split::Empty => mb(),
+                    Split::Cons(a, rest) => B::unit(Split::Cons(a, interleave(LogicT { run_logic: mb.clone() }, rest)))
+                }
+            }))
+        }))
+    }) }
+}
+
+
+// functor
+pub fn logic_fmap<B: LogicBase + 'static, Ta: Clone + 'static, Tb: Clone + 'static>(
+    f_ab: Rc<dyn Fn(Ta) -> Tb>,
+    ma: LogicT<B, Ta>
+) -> LogicT<B, Tb> {
+    logic_bind(ma, LogicTKleisli { kleisli: Rc::new(move |a: Ta| -> LogicT<B, Tb> { logic_unit(f_ab(a)) }) })
+}
+
+
+// monad
+pub struct LogicTKleisli<B: LogicBase, Ta, Tb> {
+    pub kleisli: Rc<dyn Fn(Ta) -> LogicT<B, Tb>>
+}
+
+pub type LogicKleisli<Ta, Tb> = LogicTKleisli<Identity, Ta, Tb>;
+
+impl<B: LogicBase, Ta, Tb> Clone for LogicTKleisli<B, Ta, Tb> {
+    fn clone(&self) -> Self { LogicTKleisli { kleisli: self.kleisli.clone() } }
+}
+
+// how the searches of two results are put together.
+type Join<B, Tb> = fn(LogicT<B, Tb>, LogicT<B, Tb>) -> LogicT<B, Tb>;
+
+fn bind_with<B: LogicBase + 'static, Ta: Clone + 'static, Tb: Clone + 'static>(
+    join: Join<B, Tb>,
+    ma: LogicT<B, Ta>,
+    k_ab: LogicTKleisli<B, Ta, Tb>
+) -> LogicT<B, Tb> {
+    LogicT { run_logic: Rc::new(move || -> B::M<Split<B, Tb>> {
+        let k_ab: LogicTKleisli<B, Ta, Tb> = k_ab.clone();
+        B::bind((ma.run_logic)(), Rc::new(move |r: Split<B, Ta>| -> B::M<Split<B, Tb>> {
+            match r {
+                Split::Empty => B::unit(Split::Empty),
+                Split::Cons(a, rest) => (join((k_ab.kleisli)(a), bind_with(join, rest, k_ab.clone())).run_logic)()
+            }
+        }))
+    }) }
+}
+
+// (>>=): the results of `k_ab` on the first result of `ma`, then on the rest.
+pub fn logic_bind<B: LogicBase + 'static, Ta: Clone + 'static, Tb: Clone + 'static>(
+    ma: LogicT<B, Ta>,
+    k_ab: LogicTKleisli<B, Ta, Tb>
+) -> LogicT<B, Tb> {
+    bind_with(logic_plus, ma, k_ab)
+}
+
+// (>>-): the same, taking turns between the first result and the rest.
+pub fn fair_bind<B: LogicBase + 'static, Ta: Clone + 'static, Tb: Clone + 'static>(
+    ma: LogicT<B, Ta>,
+    k_ab: LogicTKleisli<B, Ta, Tb>
+) -> LogicT<B, Tb> {
+    bind_with(interleave, ma, k_ab)
+}
+
+
+// the first result of a search and the search for the rest, if there is one.
+pub type First<B, Ta> = Option<(Ta, LogicT<B, Ta>)>;
+
+// msplit :: LogicT m a -> LogicT m (Maybe (a, LogicT m a))
+pub fn msplit<B: LogicBase + 'static, Ta: Clone + 'static>(ma: LogicT<B, Ta>) -> LogicT<B, First<B, Ta>> {
+    LogicT { run_logic: Rc::new(move || -> B::M<Split<B, First<B, Ta>>> {
+        B::bind((ma.run_logic)(), Rc::new(|r: Split<B, Ta>| -> B::M<Split<B, First<B, Ta>>> {
+            let first: First<B, Ta> = match r {
+                Split::Empty => None,
+                Split::Cons(a, rest) => Some((a, rest))
+            };
+            B::unit(Split::Cons(first, logic_empty()))
+        }))
+    }) }
+}
+
+// the inverse of `msplit`.
+pub fn reflect<B: LogicBase + 'static, Ta: Clone + 'static>(first: First<B, Ta>) -> LogicT<B, Ta> {
+    match first {
+        None => logic_empty(),
+        Some((a, rest)) => logic_plus(logic_unit(a), rest)
+    }
+}
+
+// the first result only.
+pub fn once<B: LogicBase + 'static, Ta: Clone + 'static>(ma: LogicT<B, Ta>) -> LogicT<B, Ta> {
+    LogicT { run_logic: Rc::new(move || -> B::M<Split<B, Ta>> {
+        B::bind((ma.run_logic)(), Rc::new(|r: Split<B, Ta>| -> B::M<Split<B, Ta>> {
+            match r {
+                Split::Empty => B::unit(Split::Empty),
+                Split::Cons(a, _) => B::unit(Split::Cons(a, logic_empty()))
+            }
+        }))
+    }) }
+}
+
+// soft cut: `then` on every result of `cond`, or `otherwise` if it has none.
+pub fn ifte<B: LogicBase + 'static, Ta: Clone + 'static, Tb: Clone + 'static>(
+    cond: LogicT<B, Ta>,
+    then: LogicTKleisli<B, Ta, Tb>,
+    otherwise: LogicT<B, Tb>
+) -> LogicT<B, Tb> {
+    LogicT { run_logic: Rc::new(move || -> B::M<Split<B, Tb>> {
+        let (cond, then): (LogicT<B, Ta>, LogicTKleisli<B, Ta, Tb>) = (cond.clone(), then.clone());
+        B::bind(B::fork(otherwise.run_logic.clone()), Rc::new(move |otherwise: Later<B, Split<B, Tb>>| -> B::M<Split<B, Tb>> {
+            let then: LogicTKleisli<B, Ta, Tb> = then.clone();
+            B::bind((cond.run_logic)(), Rc::new(move |r: Split<B, Ta>| -> B::M<Split<B, Tb>> {
+                match r {
+                    Split::Empty => otherwise(),
+                    Split::Cons(a, rest) => (logic_plus((then.kleisli)(a), logic_bind(rest, then.clone())).run_logic)()
+                }
+            }))
+        }))
+    }) }
+}
+
+
+// the first `n` results, in the base monad.
+pub fn observe_many<B: LogicBase + 'static, Ta: Clone + 'static>(n: usize, ma: LogicT<B, Ta>) -> B::M<Vec<Ta>> {
+    B::observe(n, ma)
+}
+
+pub fn observe_all<B: LogicBase + 'static, Ta: Clone + 'static>(ma: LogicT<B, Ta>) -> B::M<Vec<Ta>> {
+    B::observe(usize::MAX, ma)
+}
+
+// the results found so far, the last first. a continuation may run more
+// than once, so each result is consed on rather than pushed onto a `Vec`
+// they would all have to copy.
+struct Found<Ta> {
+    a: Ta,
+    before: Option<Rc<Found<Ta>>>
+}
+
+// dropped in a loop, since a long list would otherwise be dropped as deep.
+impl<Ta> Drop for Found<Ta> {
+    fn drop(&mut self) {
+        let mut before: Option<Rc<Found<Ta>>> = self.before.take();
+        while let Some(found) = before {
+            before = match Rc::try_unwrap(found) {
+                Ok(mut found) => found.before.take(),
+                Err(_) => None
+            }
+        }
+    }
+}
+
+fn found_vec<Ta: Clone>(found: &Option<Rc<Found<Ta>>>) -> Vec<Ta> {
+    let mut v: Vec<Ta> = Vec::new();
+    let mut at: Option<&Rc<Found<Ta>>> = found.as_ref();
+    while let Some(found) = at {
+        v.push(found.a.clone());
+        at = found.before.as_ref();
+    }
+    v.reverse();
+    v
+}
+
+fn observe_onto<B: LogicBase + 'static, Ta: Clone + 'static>(found: Option<Rc<Found<Ta>>>, n: usize, ma: LogicT<B, Ta>) -> B::M<Vec<Ta>> {
+    if n == 0 { return B::unit(found_vec(&found)) }
+    B::bind((ma.run_logic)(), Rc::new(move |r: Split<B, Ta>| -> B::M<Vec<Ta>> {
+        match r {
+            Split::Empty => B::unit(found_vec(&found)),
+            Split::Cons(a, rest) => observe_onto(Some(Rc::new(Found { a, before: found.clone() })), n - 1, rest)
+        }
+    }))
+}
+
+
+// without a base the results can be taken one at a time, in a loop.
+pub struct LogicIter<Ta> {
+    rest: Option<Logic<Ta>>
+}
+
+impl<Ta> Logic<Ta> {
+    pub fn iter(&self) -> LogicIter<Ta> { LogicIter { rest: Some(self.clone()) } }
+}
+
+impl<Ta> Iterator for LogicIter<Ta> {
+    type Item = Ta;
+
+    fn next(&mut self) -> Option<Ta> {
+        let ma: Logic<Ta> = self.rest.take()?;
+        match (ma.run_logic)() {
+            Split::Empty => None,
+            Split::Cons(a, rest) => {
+                self.rest = Some(rest);
+                Some(a)
+            }
+        }
+    }
+}
+
+
+// do-notation. internally every rule carries the module to take the monad
+// from and the pointer its kleisli arrows are stored in.
+#[macro_export]
+macro_rules! logic_do {
+    // trailing comma
+    (@[$($m:tt)*][$($p:tt)*]) => {};
+
+    (@[$($m:tt)*][$($p:tt)*] $v:ident = $e:expr,  $($rest:tt)*) => { (|$v| { $crate::logic_do!(@[$($m)*][$($p)*] $($rest)*) })($e) };
+
+    (@[$($m:tt)*][$($p:tt)*] $v:ident <- $e:expr, $($rest:tt)*) => {
+        $($m)*::logic_bind(
+            $e,
+            $($m)*::LogicTKleisli {
+                kleisli: $($p)*::new( move |$v| { $crate::logic_do!(@[$($m)*][$($p)*] $($rest)*) } )
+            }
+        )
+    };
+
+    (@[$($m:tt)*][$($p:tt)*] $e:expr, $($rest:tt)*) => {
+        $($m)*::logic_bind(
+            $e,
+            $($m)*::LogicTKleisli {
+                kleisli: $($p)*::new( move |_| { $crate::logic_do!(@[$($m)*][$($p)*] $($rest)*) } )
+            }
+        )
+    };
+
+    (@[$($m:tt)*][$($p:tt)*] $e:expr) => { $e };
+
+    ($($rest:tt)*) => { $crate::logic_do!(@[$crate::monads::logic][$crate::prelude::Rc] $($rest)*) };
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use crate::monads::laws::{check_functor_laws, check_monad_laws};
+    use crate::monads::prop::Config;
+
+    fn range<B: LogicBase + 'static>(lo: i64, hi: i64) -> LogicT<B, i64> {
+        (lo..hi).rev().fold(logic_empty(), |rest: LogicT<B, i64>, x: i64| logic_plus(logic_unit(x), rest))
+    }
+
+    // n, n + step, n + 2 step, ...
+    fn from(n: i64, step: i64) -> Logic<i64> {
+        LogicT { run_logic: Rc::new(move || -> Split<Identity, i64> {
+            (logic_plus(logic_unit(n), from(n + step, step)).run_logic)()
+        }) }
+    }
+
+    #[test]
+    fn test_search() {
+        let triples: Logic<(i64, i64, i64)> = logic_do!(
+            a <- range(1, 20),
+            b <- range(a, 20),
+            c <- range(b, 30),
+            logic_guard(a * a + b * b == c * c),
+            logic_unit((a, b, c))
+        );
+        assert_eq!(triples.iter().take(3).collect::<Vec<(i64, i64, i64)>>(), vec![(3, 4, 5), (5, 12, 13), (6, 8, 10)]);
+        assert_eq!(observe_many(2, triples.clone()), vec![(3, 4, 5), (5, 12, 13)]);
+        assert_eq!(observe_all(triples).len(), 6);
+    }
+
+    #[test]
+    fn test_interleave() {
+        let plus: Logic<i64> = logic_plus(from(0, 2), from(1, 2));
+        assert_eq!(plus.iter().take(6).collect::<Vec<i64>>(), vec![0, 2, 4, 6, 8, 10]);
+
+        let fair: Logic<i64> = interleave(from(0, 2), from(1, 2));
+        assert_eq!(fair.iter().take(6).collect::<Vec<i64>>(), vec![0, 1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn test_fair_bind() {
+        let odds_plus = LogicKleisli { kleisli: Rc::new(|n: i64| logic_fmap(Rc::new(move |a: i64| a + n), from(1, 2))) };
+        let zero_one = || -> Logic<i64> { logic_plus(logic_unit(0), logic_unit(1)) };
+
+        // the results for 0 never run out, so >>= never gets to 1.
+        assert_eq!(logic_bind(zero_one(), odds_plus.clone()).iter().take(3).collect::<Vec<i64>>(), vec![1, 3, 5]);
+        assert_eq!(fair_bind(zero_one(), odds_plus).iter().take(3).collect::<Vec<i64>>(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_once_ifte() {
+        assert_eq!(once(from(7, 1)).iter().collect::<Vec<i64>>(), vec![7]);
+
+        // a number is prime unless some smaller number divides it.
+        let primes: Logic<i64> = logic_do!(
+            n <- range(2, 30),
+            ifte(
+                logic_do!(d <- range(2, n), logic_guard(n % d == 0), logic_unit(d)),
+                LogicKleisli { kleisli: Rc::new(|_: i64| logic_empty()) },
+                logic_unit(n)
+            )
+        );
+        assert_eq!(primes.iter().collect::<Vec<i64>>(), vec![2, 3, 5, 7, 11, 13, 17, 19, 23, 29]);
+    }
+
+    #[test]
+    fn test_msplit() {
+        let first: Vec<Option<(i64, Logic<i64>)>> = msplit(from(3, 1)).iter().collect();
+        assert_eq!(first.len(), 1);
+        let (a, rest): (i64, Logic<i64>) = first[0].clone().unwrap();
+        assert_eq!((a, rest.iter().next()), (3, Some(4)));
+        assert!(msplit(logic_empty::<Identity, i64>()).iter().next().unwrap().is_none());
+
+        // reflect undoes msplit.
+        let m: Logic<i64> = logic_bind(msplit(range(0, 5)), LogicKleisli { kleisli: Rc::new(reflect) });
+        assert_eq!(m.iter().collect::<Vec<i64>>(), vec![0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_observe_many() {
+        let squares: Logic<i64> = logic_fmap(Rc::new(|n: i64| n * n), from(0, 1));
+        let found: Vec<i64> = observe_many(100_000, squares);
+        assert_eq!((found.len(), found[99_999]), (100_000, 99_999 * 99_999));
+        let pairs: Logic<(i64, i64)> = logic_do!(a <- range(0, 1000), b <- range(0, 100), logic_unit((a, b)));
+        assert_eq!(observe_all(pairs).last(), Some(&(999, 99)));
+    }
+
+    #[test]
+    #[cfg(feature = "state")]
+    fn test_state() {
+        use crate::monads::state::{get, put};
+
+        type Search<Ta> = LogicT<StateBase<i64>, Ta>;
+
+        // every branch adds to the state it found at its choice point.
+        let m: Search<(i64, i64)> = logic_do!(
+            x <- range(1, 4),
+            s <- logic_lift(get),
+            logic_lift(move || put(s + 10 * x)),
+            y <- logic_plus(logic_unit(0), logic_unit(1)),
+            s <- logic_lift(get),
+            logic_lift(move || put(s + y)),
+            s <- logic_lift(get),
+            logic_unit((x, s))
+        );
+        let (found, _): (Vec<(i64, i64)>, i64) = (observe_all(m).run_state)(100);
+        assert_eq!(found, vec![(1, 110), (1, 111), (2, 120), (2, 121), (3, 130), (3, 131)]);
+
+        // a branch that fails leaves nothing behind for the next one.
+        let n: Search<i64> = logic_plus(
+            logic_do!(logic_lift(|| put(1)), logic_empty()),
+            logic_lift(get)
+        );
+        assert_eq!((observe_all(n).run_state)(0).0, vec![0]);
+    }
+
+    // a base that only delays, which takes the default `observe`.
+    struct Delay;
+
+    impl LogicBase for Delay {
+        type M<Ta> = Rc<dyn Fn() -> Ta>;
+
+        fn unit<Ta: Clone + 'static>(a: Ta) -> Rc<dyn Fn() -> Ta> { Rc::new(move || a.clone()) }
+
+        fn bind<Ta: 'static, Tb: 'static>(ma: Rc<dyn Fn() -> Ta>, k_ab: Rc<dyn Fn(Ta) -> Rc<dyn Fn() -> Tb>>) -> Rc<dyn Fn() -> Tb> {
+            Rc::new(move || k_ab(ma())())
+        }
+
+        fn fork<Ta: 'static>(later: Later<Self, Ta>) -> Rc<dyn Fn() -> Later<Self, Ta>> { Rc::new(move || later.clone()) }
+    }
+
+    #[test]
+    fn test_default_observe() {
+        let m: LogicT<Delay, i64> = logic_do!(a <- range(0, 10), logic_guard(a % 3 == 0), logic_unit(a));
+        assert_eq!(observe_many(2, m.clone())(), vec![0, 3]);
+        assert_eq!(observe_all(m.clone())(), vec![0, 3, 6, 9]);
+
+        // what is observed can be run again, and gives the same.
+        let all: Rc<dyn Fn() -> Vec<i64>> = observe_all(m);
+        assert_eq!((all(), all()), (vec![0, 3, 6, 9], vec![0, 3, 6, 9]));
+    }
+
+    #[test]
+    #[cfg(feature = "state")]
+    fn test_state_observe_many() {
+        use crate::monads::state::{get, put};
+
+        // each result counts itself onto the state of its branch.
+        let pairs: LogicT<StateBase<i64>, (i64, i64)> = logic_do!(
+            a <- range(0, 1000),
+            b <- range(0, 100),
+            s <- logic_lift(get),
+            logic_lift(move || put(s + 1)),
+            logic_unit((a, b))
+        );
+        let (found, s): (Vec<(i64, i64)>, i64) = (observe_many(100_000, pairs.clone()).run_state)(0);
+        assert_eq!((found.len(), found.last(), s), (100_000, Some(&(999, 99)), 1));
+        assert_eq!((observe_all(pairs).run_state)(0).0.len(), 100_000);
+    }
+
+    #[test]
+    fn test_laws() {
+        let gen = |p: &(i64, i64)| -> Logic<i64> { logic_plus(logic_unit(p.0), logic_unit(p.1)) };
+        check_functor_laws(&Config::default(), gen, logic_fmap).unwrap();
+        check_monad_laws(
+            &Config::default(),
+            gen,
+            logic_unit,
+            |m, k| logic_bind(m, LogicTKleisli { kleisli: k })
+        ).unwrap();
+    }
+}
@@ -1,6 +1,58 @@
 pub mod monoid;
+#[cfg(all(feature = "alloc", any(test, feature = "testing")))]
+pub mod prop;
+#[cfg(all(feature = "alloc", any(test, feature = "testing")))]
+pub mod observe;
+#[cfg(all(feature = "alloc", any(test, feature = "testing")))]
+pub mod laws;
+#[cfg(feature = "alloc")]
+pub mod identity;
 
+#[cfg(feature = "reader")]
 pub mod reader;
+#[cfg(feature = "state")]
 pub mod state;
+#[cfg(feature = "writer")]
 pub mod writer;
+#[cfg(feature = "cont")]
 pub mod cont;
+#[cfg(feature = "effects")]
+pub mod effect;
+#[cfg(feature = "generators")]
+pub mod generator;
+#[cfg(feature = "amb")]
+pub mod amb;
+#[cfg(feature = "logic")]
+pub mod logic;
+#[cfg(feature = "scheduler")]
+pub mod scheduler;
+#[cfg(feature = "exceptions")]
+pub mod exception;
+#[cfg(feature = "cps")]
+pub mod cps;
+#[cfg(feature = "future")]
+pub mod future;
+#[cfg(feature = "io")]
+pub mod io;
+#[cfg(feature = "free")]
+pub mod free;
+#[cfg(feature = "free_ap")]
+pub mod free_ap;
+#[cfg(feature = "operational")]
+pub mod operational;
+#[cfg(any(feature = "operational", feature = "free_ap"))]
+pub mod instr;
+#[cfg(feature = "validation")]
+pub mod validation;
+
+#[cfg(any(feature = "state", feature = "reader"))]
+pub mod inline;
+
+#[cfg(any(feature = "state", feature = "reader", feature = "cont"))]
+pub mod once;
+
+#[cfg(feature = "writer")]
+pub mod lazy;
+
+#[cfg(feature = "sync")]
+pub mod sync;
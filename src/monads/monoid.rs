@@ -1,3 +1,11 @@
+#[cfg(feature = "alloc")]
+use alloc::{collections::BTreeMap, format, string::{String, ToString}, vec, vec::Vec};
+#[cfg(feature = "std")]
+use core::hash::Hash;
+#[cfg(feature = "std")]
+use std::{collections::HashMap, thread};
+
+
 // A monoid is defined by a type T of which it
 // returns an `empty` version thereof
 // can concatenate onto itself.
@@ -5,19 +13,51 @@ pub trait Monoid {
     type T;
     fn mempty() -> Self::T;
     fn mappend(&self, other: Self::T) -> Self::T;
+
+    // concatenates onto itself in place. the default goes through `mappend`,
+    // containers override it to grow their own storage instead of copying it.
+    fn mappend_assign(&mut self, other: Self::T) where Self: Monoid<T = Self> + Sized {
+        *self = self.mappend(other);
+    }
+}
+
+
+// A semigroup can only concatenate: there may be no `empty` to start from,
+// as with a non-empty list of errors. every monoid is one.
+pub trait Semigroup: Sized {
+    fn sappend(self, other: Self) -> Self;
+}
+
+impl<M: Monoid<T = M>> Semigroup for M {
+    fn sappend(mut self, other: M) -> M {
+        self.mappend_assign(other);
+        self
+    }
 }
 
 
 // containers, in general, are monoids
 
-// strings
+// strings, as lines of a log. the empty string contributes no line,
+// otherwise it would not be an identity for the newline join.
+#[cfg(feature = "alloc")]
 impl Monoid for String {
     type T = String;
     fn mempty() -> Self::T { "".to_string() }
-    fn mappend(&self, other: Self::T) -> Self::T { format!("{}\n{}", self, other) }
+    fn mappend(&self, other: Self::T) -> Self::T {
+        if self.is_empty() { return other }
+        if other.is_empty() { return self.clone() }
+        format!("{}\n{}", self, other)
+    }
+    fn mappend_assign(&mut self, other: Self::T) {
+        if other.is_empty() { return }
+        if !self.is_empty() { self.push('\n'); }
+        self.push_str(&other);
+    }
 }
 
 // lists
+#[cfg(feature = "alloc")]
 impl<A: Clone> Monoid for Vec<A> {
     type T = Vec<A>;
     fn mempty() -> Self::T { vec![] }
@@ -25,6 +65,147 @@ impl<A: Clone> Monoid for Vec<A> {
         let mut ret: Self::T = vec![];
         for el in self { ret.push(el.clone()); }
         for el in other { ret.push(el.clone()); }
-        return ret
+        ret
+    }
+    fn mappend_assign(&mut self, other: Self::T) { self.extend(other); }
+}
+
+
+// maps, as the union of their entries. values found under the same key
+// are combined with their own monoid, left before right.
+#[cfg(feature = "alloc")]
+impl<K: Ord + Clone, V: Monoid<T = V> + Clone> Monoid for BTreeMap<K, V> {
+    type T = BTreeMap<K, V>;
+    fn mempty() -> Self::T { BTreeMap::new() }
+    fn mappend(&self, other: Self::T) -> Self::T {
+        let mut ret: Self::T = self.clone();
+        ret.mappend_assign(other);
+        ret
+    }
+    fn mappend_assign(&mut self, other: Self::T) {
+        for (k, v) in other {
+            match self.get_mut(&k) {
+                Some(mine) => mine.mappend_assign(v),
+                None => { self.insert(k, v); }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Eq + Hash + Clone, V: Monoid<T = V> + Clone> Monoid for HashMap<K, V> {
+    type T = HashMap<K, V>;
+    fn mempty() -> Self::T { HashMap::new() }
+    fn mappend(&self, other: Self::T) -> Self::T {
+        let mut ret: Self::T = self.clone();
+        ret.mappend_assign(other);
+        ret
+    }
+    fn mappend_assign(&mut self, other: Self::T) {
+        for (k, v) in other {
+            match self.get_mut(&k) {
+                Some(mine) => mine.mappend_assign(v),
+                None => { self.insert(k, v); }
+            }
+        }
+    }
+}
+
+
+// maps every element into the monoid and concatenates the results left to right.
+// the first element seeds the fold, so `mempty` is only returned for empty input.
+pub fn fold_map<A, M: Monoid<T = M>>(xs: &[A], f: impl Fn(&A) -> M) -> M {
+    let mut it = xs.iter();
+    match it.next() {
+        None => M::mempty(),
+        Some(x) => it.fold(f(x), |mut acc, x| { acc.mappend_assign(f(x)); acc }),
+    }
+}
+
+
+// the same as `fold_map`, but the input is split into one contiguous chunk per
+// available thread. since `mappend` is associative, folding each chunk on its own
+// and then concatenating the partial results in order gives the same answer.
+#[cfg(feature = "std")]
+pub fn fold_map_par<A: Sync, M: Monoid<T = M> + Send>(xs: &[A], f: impl Fn(&A) -> M + Sync) -> M {
+    let threads: usize = thread::available_parallelism().map_or(1, |n| n.get());
+    let chunk: usize = xs.len().div_ceil(threads).max(1);
+    let f = &f;
+
+    thread::scope(|scope| {
+        let partials: Vec<thread::ScopedJoinHandle<M>> = xs
+            .chunks(chunk)
+            .map(|c| scope.spawn(move || fold_map(c, f)))
+            .collect();
+
+        partials
+            .into_iter()
+            .map(|h| h.join().expect("fold_map_par worker panicked"))
+            .reduce(|mut acc, m| { acc.mappend_assign(m); acc })
+            .unwrap_or_else(M::mempty)
+    })
+}
+
+
+// tests
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use super::*;
+    use crate::monads::laws::check_monoid_laws;
+    use crate::monads::prop::Config;
+
+    #[test]
+    fn test_mappend_assign() {
+        let mut s0: String = "hello".to_string();
+        s0.mappend_assign("goodbye".to_string());
+        assert_eq!(s0, "hello".to_string().mappend("goodbye".to_string()));
+
+        let mut v0: Vec<i64> = vec![1, 2];
+        v0.mappend_assign(vec![3]);
+        assert_eq!(v0, vec![1, 2].mappend(vec![3]));
+    }
+
+    #[test]
+    fn test_fold_map() {
+        let xs: Vec<i64> = (0..5).collect();
+        assert_eq!(fold_map(&xs, |x| vec![2 * x]), vec![0, 2, 4, 6, 8]);
+        assert_eq!(fold_map(&xs, |x| x.to_string()), "0\n1\n2\n3\n4".to_string());
+        assert_eq!(fold_map(&[] as &[i64], |x| vec![*x]), vec![]);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_fold_map_par() {
+        let xs: Vec<i64> = (0..100_000).collect();
+        assert_eq!(fold_map_par(&xs, |x| vec![x + 1]), fold_map(&xs, |x| vec![x + 1]));
+        assert_eq!(fold_map_par(&xs[..7], |x| x.to_string()), fold_map(&xs[..7], |x| x.to_string()));
+        assert_eq!(fold_map_par(&[] as &[i64], |x| x.to_string()), "".to_string());
+    }
+
+    #[test]
+    fn test_laws() {
+        check_monoid_laws::<String>(&Config::default()).unwrap();
+        check_monoid_laws::<Vec<i64>>(&Config::default()).unwrap();
+    }
+
+    #[test]
+    fn test_maps() {
+        let m0: BTreeMap<&str, Vec<i64>> = [("a", vec![1]), ("b", vec![2])].into_iter().collect();
+        let m1: BTreeMap<&str, Vec<i64>> = [("b", vec![3]), ("c", vec![4])].into_iter().collect();
+        let m2: BTreeMap<&str, Vec<i64>> = [("a", vec![1]), ("b", vec![2, 3]), ("c", vec![4])].into_iter().collect();
+        assert_eq!(m0.mappend(m1), m2);
+
+        check_monoid_laws::<BTreeMap<i64, Vec<i64>>>(&Config::default()).unwrap();
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_hash_maps() {
+        let m0: HashMap<&str, String> = [("a", "x".to_string())].into_iter().collect();
+        let m1: HashMap<&str, String> = [("a", "y".to_string()), ("b", "z".to_string())].into_iter().collect();
+        let m2: HashMap<&str, String> = [("a", "x\ny".to_string()), ("b", "z".to_string())].into_iter().collect();
+        assert_eq!(m0.mappend(m1), m2);
+
+        check_monoid_laws::<HashMap<i64, String>>(&Config::default()).unwrap();
     }
 }
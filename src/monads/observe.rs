@@ -0,0 +1,73 @@
+use core::fmt::Debug;
+
+use super::prop::{Arbitrary, Config, Counterexample, Verdict, expect_eq, forall};
+
+
+// Observational equality.
+// Monads that wrap functions (state, reader, cont) cannot be compared directly,
+// but two of them are equal when no input tells them apart. An instance says
+// what an input is (a state, a configuration, a continuation) and what running
+// the monad on it produces.
+pub trait ObserveEq {
+    type Input: Arbitrary;
+    type Output: PartialEq + Debug;
+    fn observe(&self, input: &Self::Input) -> Self::Output;
+}
+
+
+// compares two monadic values on a single input.
+pub fn observe_on<M: ObserveEq>(lhs: &M, rhs: &M, input: &M::Input) -> Verdict {
+    expect_eq(lhs.observe(input), rhs.observe(input))
+}
+
+
+// compares two monadic values on `cfg.cases` generated inputs,
+// reporting the smallest input on which they differ.
+pub fn observe_eq<M: ObserveEq>(cfg: &Config, lhs: &M, rhs: &M) -> Result<(), Counterexample> {
+    forall(cfg, "observationally equal", |input: &M::Input| observe_on(lhs, rhs, input))
+}
+
+
+// tests
+#[cfg(all(test, feature = "state", feature = "reader", feature = "cont"))]
+mod tests {
+    use alloc::rc::Rc;
+
+    use super::*;
+    use crate::monads::cont::{ContKleisli, ContMonad, cont_bind, cont_fmap, cont_unit};
+    use crate::monads::reader::{ReaderMonad, reader_fmap, reader_unit};
+    use crate::monads::state::{StateMonad, get, put, state_bind, StateKleisli};
+
+    #[test]
+    fn test_state() {
+        let incr: StateMonad<i64, ()> = state_bind(
+            get(), StateKleisli { kleisli: Rc::new(|s: i64| put(s.wrapping_add(1))) }
+        );
+        let incr_by_hand: StateMonad<i64, ()> = StateMonad { run_state: Rc::new(|s: i64| ((), s.wrapping_add(1))) };
+        assert_eq!(observe_eq(&Config::default(), &incr, &incr_by_hand), Ok(()));
+
+        let clamp: StateMonad<i64, ()> = StateMonad { run_state: Rc::new(|s: i64| ((), s.min(10).wrapping_add(1))) };
+        let failure = observe_eq(&Config::default(), &incr, &clamp).unwrap_err();
+        assert_eq!(failure.input, "11");
+        assert_eq!(failure.reason, "lhs: ((), 12)\nrhs: ((), 11)");
+    }
+
+    #[test]
+    fn test_reader() {
+        let r0: ReaderMonad<i64, bool> = reader_fmap(Rc::new(|x: i64| x > 0), ReaderMonad { run_reader: Rc::new(|cfg: &i64| *cfg) });
+        let r1: ReaderMonad<i64, bool> = reader_unit(true);
+        let failure = observe_eq(&Config::default(), &r0, &r1).unwrap_err();
+        assert_eq!(failure.input, "0");
+    }
+
+    #[test]
+    fn test_cont() {
+        let m0: ContMonad<i64, i64> = cont_fmap(Rc::new(|x: i64| x * 2), cont_unit(21));
+        let m1: ContMonad<i64, i64> = cont_bind(cont_unit(7), ContKleisli { kleisli: Rc::new(|x: i64| cont_unit(x * 6)) });
+        assert_eq!(observe_eq(&Config::default(), &m0, &m1), Ok(()));
+
+        // indistinguishable with the identity continuation, but not in general.
+        let m2: ContMonad<i64, i64> = ContMonad { run_cont: Rc::new(|k: Rc<dyn Fn(i64) -> i64>| if k(42) == 42 { 42 } else { 0 }) };
+        assert!(observe_eq(&Config::default(), &m0, &m2).is_err());
+    }
+}
@@ -0,0 +1,107 @@
+use alloc::boxed::Box;
+
+
+// a suspended computation that calls its continuation at most once.
+pub type RunCont<Tr, Ta> = Box<dyn FnOnce(Box<dyn FnOnce(Ta) -> Tr>) -> Tr>;
+
+
+pub struct ContMonad<Tr, Ta> {
+    pub run_cont: RunCont<Tr, Ta>
+}
+
+
+pub fn cont_unit<Tr, Ta: 'static>(a: Ta) -> ContMonad<Tr, Ta> {
+    ContMonad { run_cont: Box::new(
+        move |f: Box<dyn FnOnce(Ta) -> Tr>| -> Tr { f(a) }
+    ) }
+}
+
+
+pub fn cont_eval<Tr>(
+    ma: ContMonad<Tr, Tr>
+) -> Tr {
+    (ma.run_cont)(Box::new( move |x| x))
+}
+
+
+// functor
+pub fn cont_fmap<Tr: 'static, Ta: 'static, Tb: 'static>(
+    f_ab: impl FnOnce(Ta) -> Tb + 'static,
+    ma: ContMonad<Tr, Ta>
+) -> ContMonad<Tr, Tb> {
+    ContMonad { run_cont: Box::new( move |f_br: Box<dyn FnOnce(Tb) -> Tr>| -> Tr {
+        (ma.run_cont)(Box::new(move |a: Ta| -> Tr { f_br(f_ab(a)) } ))
+    } ) }
+}
+
+
+// applicative
+pub fn cont_apply<Tr: 'static, Ta: 'static, Tb: 'static>(
+    mf: ContMonad<Tr, Box<dyn FnOnce(Ta) -> Tb>>,
+    ma: ContMonad<Tr, Ta>
+) -> ContMonad<Tr, Tb> {
+    ContMonad { run_cont: Box::new( move |f_br: Box<dyn FnOnce(Tb) -> Tr>| -> Tr {
+        (mf.run_cont)(Box::new(move |f_ab: Box<dyn FnOnce(Ta) -> Tb>| -> Tr {
+            (ma.run_cont)(Box::new( move |a: Ta| -> Tr { f_br(f_ab(a)) } ) )
+        } ) )
+    } ) }
+}
+
+
+// monad
+pub struct ContKleisli<Tr, Ta, Tb> {
+    pub kleisli: Box<dyn FnOnce(Ta) -> ContMonad<Tr, Tb>>
+}
+
+pub fn cont_bind<Tr: 'static, Ta: 'static, Tb: 'static>(
+    ma: ContMonad<Tr, Ta>,
+    k_ab: ContKleisli<Tr, Ta, Tb>
+) -> ContMonad<Tr, Tb> {
+    ContMonad { run_cont: Box::new( move |f_br: Box<dyn FnOnce(Tb) -> Tr>| -> Tr {
+        (ma.run_cont)(Box::new( move |a: Ta| -> Tr { ((k_ab.kleisli)(a).run_cont)(f_br) } ))
+    } ) }
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use crate::cont_do;
+
+    #[derive(Debug, PartialEq)]
+    struct Token(String);
+
+    #[test]
+    fn test_fmap_apply() {
+        let m0 = cont_fmap(|t: Token| t.0.len(), cont_unit(Token("abc".to_string())));
+        assert_eq!(cont_eval(m0), 3);
+
+        let m1 = cont_apply(
+            cont_unit(Box::new(|t: Token| vec![t]) as Box<dyn FnOnce(Token) -> Vec<Token>>),
+            cont_unit(Token("x".to_string()))
+        );
+        assert_eq!(cont_eval(m1), vec![Token("x".to_string())]);
+    }
+
+    #[test]
+    fn test_do() {
+        let calc: ContMonad<Token, Token> = cont_do!(once =>
+            a <- cont_unit(Token("a".to_string())),
+            b <- cont_unit(Token("b".to_string())),
+            cont_unit(Token(a.0 + &b.0))
+        );
+        assert_eq!(cont_eval(calc), Token("ab".to_string()));
+
+        // the answer type may be move-only too, and the continuation may be dropped.
+        let early: ContMonad<Token, i64> = ContMonad { run_cont: Box::new(|_| Token("early".to_string())) };
+        let calc: ContMonad<Token, Token> = cont_do!(once =>
+            x <- early,
+            cont_unit(Token(x.to_string()))
+        );
+        assert_eq!(cont_eval(calc), Token("early".to_string()));
+    }
+}
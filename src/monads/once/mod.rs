@@ -0,0 +1,14 @@
+// Single-shot variants of the function-wrapping monads.
+// Their functions are `Box<dyn FnOnce>`, so a computation runs at most once,
+// and in exchange values are moved through every bind instead of cloned.
+// This is what allows move-only resources (file handles, boxes, anything
+// without `Clone`) to live in the state, the result or the answer type.
+// The do-notation builds them when given a leading `once =>`.
+pub use alloc::boxed::Box;
+
+#[cfg(feature = "reader")]
+pub mod reader;
+#[cfg(feature = "state")]
+pub mod state;
+#[cfg(feature = "cont")]
+pub mod cont;
@@ -0,0 +1,91 @@
+use alloc::boxed::Box;
+
+
+// the configuration is only ever lent out, so it need not be `Clone`.
+pub struct ReaderMonad<Tcfg, Ta> {
+    pub run_reader: Box<dyn FnOnce(&Tcfg) -> Ta>
+}
+
+
+pub fn reader_unit<Tcfg, Ta: 'static>(a: Ta) -> ReaderMonad<Tcfg, Ta> {
+    ReaderMonad { run_reader: Box::new(move |_: &Tcfg| -> Ta { a } ) }
+}
+
+
+// functor
+pub fn reader_fmap<Tcfg: 'static, Ta: 'static, Tb: 'static>(
+    f_ab: impl FnOnce(Ta) -> Tb + 'static,
+    ma: ReaderMonad<Tcfg, Ta>
+) -> ReaderMonad<Tcfg, Tb> {
+    ReaderMonad { run_reader: Box::new( move |cfg: &Tcfg| -> Tb { f_ab((ma.run_reader)(cfg)) } ) }
+}
+
+
+// applicative
+pub fn reader_apply<Tcfg: 'static, Ta: 'static, Tb: 'static>(
+    mf: ReaderMonad<Tcfg, Box<dyn FnOnce(Ta) -> Tb>>,
+    ma: ReaderMonad<Tcfg, Ta>
+) -> ReaderMonad<Tcfg, Tb> {
+    ReaderMonad { run_reader: Box::new( move |cfg: &Tcfg| -> Tb { ((mf.run_reader)(cfg))((ma.run_reader)(cfg)) } ) }
+}
+
+
+// monad
+pub struct ReaderKleisli<Tcfg, Ta, Tb> {
+    pub kleisli: Box<dyn FnOnce(Ta) -> ReaderMonad<Tcfg, Tb>>
+}
+
+pub fn reader_bind<Tcfg: 'static, Ta: 'static, Tb: 'static>(
+    ma: ReaderMonad<Tcfg, Ta>,
+    k_ab: ReaderKleisli<Tcfg, Ta, Tb>
+) -> ReaderMonad<Tcfg, Tb> {
+    ReaderMonad { run_reader: Box::new( move |cfg: &Tcfg| -> Tb { ((k_ab.kleisli)((ma.run_reader)(cfg)).run_reader)(cfg) } ) }
+}
+
+
+// extracts a copy of the configuration; only possible when it can be cloned.
+pub fn load<Tcfg: Clone>() -> ReaderMonad<Tcfg, Tcfg> {
+    ReaderMonad { run_reader: Box::new(|cfg: &Tcfg| -> Tcfg { cfg.clone() } ) }
+}
+
+
+// extracts something computed from the configuration.
+pub fn asks<Tcfg, Ta>(f: impl FnOnce(&Tcfg) -> Ta + 'static) -> ReaderMonad<Tcfg, Ta> {
+    ReaderMonad { run_reader: Box::new(f) }
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::{String, ToString};
+    use crate::reader_do;
+
+    // neither the configuration nor the results can be cloned.
+    struct Config {
+        name: String,
+        retries: Box<u32>,
+    }
+
+    #[test]
+    fn test_fmap_apply() {
+        let r0 = reader_fmap(|n: Box<u32>| *n * 2, asks(|cfg: &Config| cfg.retries.clone()));
+        let r1 = reader_apply(
+            asks(|cfg: &Config| { let name = cfg.name.clone(); Box::new(move |n: u32| (name, n)) as Box<dyn FnOnce(u32) -> (String, u32)> }),
+            r0
+        );
+        let cfg = Config { name: "db".to_string(), retries: Box::new(3) };
+        assert_eq!((r1.run_reader)(&cfg), ("db".to_string(), 6));
+    }
+
+    #[test]
+    fn test_do() {
+        let describe: ReaderMonad<Config, String> = reader_do!(once =>
+            name <- asks(|cfg: &Config| cfg.name.clone()),
+            retries <- asks(|cfg: &Config| *cfg.retries),
+            reader_unit(name + &":".repeat(retries as usize))
+        );
+        assert_eq!((describe.run_reader)(&Config { name: "db".to_string(), retries: Box::new(2) }), "db::".to_string());
+    }
+}
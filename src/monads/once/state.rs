@@ -0,0 +1,147 @@
+use alloc::boxed::Box;
+
+
+pub struct StateMonad<Ts, Ta> {
+    pub run_state: Box<dyn FnOnce(Ts) -> (Ta, Ts)>
+}
+
+
+pub fn state_unit<Ts, Ta: 'static>(a: Ta) -> StateMonad<Ts, Ta> {
+    StateMonad { run_state: Box::new( move |s: Ts| -> (Ta, Ts) { (a, s) } ) }
+}
+
+
+// functor
+pub fn state_fmap<Ts: 'static, Ta: 'static, Tb: 'static>(
+    f_ab: impl FnOnce(Ta) -> Tb + 'static,
+    ma: StateMonad<Ts, Ta>
+) -> StateMonad<Ts, Tb> {
+    StateMonad { run_state:
+        Box::new( move |s: Ts| -> (Tb, Ts) {
+            let a_s: (Ta, Ts) = (ma.run_state)(s);
+            (f_ab(a_s.0), a_s.1)
+        })
+    }
+}
+
+
+// applicative
+pub fn state_apply<Ts: 'static, Ta: 'static, Tb: 'static>(
+    mf: StateMonad<Ts, Box<dyn FnOnce(Ta) -> Tb>>,
+    ma: StateMonad<Ts, Ta>
+) -> StateMonad<Ts, Tb> {
+    StateMonad { run_state:
+        Box::new( move |s: Ts| -> (Tb, Ts) {
+            let f_s: (Box<dyn FnOnce(Ta) -> Tb>, Ts) = (mf.run_state)(s);
+            let a_s: (Ta, Ts) = (ma.run_state)(f_s.1);
+            ((f_s.0)(a_s.0), a_s.1)
+        })
+    }
+}
+
+
+// monad
+pub struct StateKleisli<Ts, Ta, Tb> {
+    pub kleisli: Box<dyn FnOnce(Ta) -> StateMonad<Ts, Tb>>
+}
+
+pub fn state_bind<Ts: 'static, Ta: 'static, Tb: 'static>(
+    ma: StateMonad<Ts, Ta>,
+    k_ab: StateKleisli<Ts, Ta, Tb>
+) -> StateMonad<Ts, Tb> {
+    StateMonad { run_state:
+        Box::new( move |s: Ts| -> (Tb, Ts) {
+            let a_s: (Ta, Ts) = (ma.run_state)(s);
+            let mb: StateMonad<Ts, Tb> = (k_ab.kleisli)(a_s.0);
+            (mb.run_state)(a_s.1)
+        })
+    }
+}
+
+
+// the most general state action, from a function that threads the state through.
+pub fn state<Ts, Ta>(f: impl FnOnce(Ts) -> (Ta, Ts) + 'static) -> StateMonad<Ts, Ta> {
+    StateMonad { run_state: Box::new(f) }
+}
+
+
+// extracts a copy of the state; only possible when it can be cloned.
+pub fn get<Ts: Clone>() -> StateMonad<Ts, Ts> {
+    StateMonad { run_state: Box::new( |s: Ts| -> (Ts, Ts) { (s.clone(), s) } ) }
+}
+
+
+// extracts something computed from a borrow of the state.
+pub fn gets<Ts, Ta>(f: impl FnOnce(&Ts) -> Ta + 'static) -> StateMonad<Ts, Ta> {
+    StateMonad { run_state: Box::new( move |s: Ts| -> (Ta, Ts) { (f(&s), s) } ) }
+}
+
+
+// returns the state into the monadic context, dropping the old one.
+pub fn put<Ts: 'static>(s: Ts) -> StateMonad<Ts, ()> {
+    StateMonad { run_state: Box::new( move |_: Ts| -> ((), Ts) { ((), s) } ) }
+}
+
+
+// swaps in a new state and moves the old one out as the result.
+pub fn replace<Ts: 'static>(s: Ts) -> StateMonad<Ts, Ts> {
+    StateMonad { run_state: Box::new( move |old: Ts| -> (Ts, Ts) { (old, s) } ) }
+}
+
+
+pub fn modify<Ts>(f: impl FnOnce(Ts) -> Ts + 'static) -> StateMonad<Ts, ()> {
+    StateMonad { run_state: Box::new( move |s: Ts| -> ((), Ts) { ((), f(s)) } ) }
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use crate::state_do;
+
+    // a resource that can be neither cloned nor copied.
+    #[derive(Debug, PartialEq)]
+    struct Handle {
+        written: Vec<u8>
+    }
+
+    fn write(bytes: &'static [u8]) -> StateMonad<Handle, usize> {
+        state(move |mut h: Handle| { h.written.extend_from_slice(bytes); (bytes.len(), h) })
+    }
+
+    #[test]
+    fn test_fmap_apply() {
+        let s0 = state_fmap(|b: Box<i64>| *b + 1, state_unit(Box::new(10)));
+        assert_eq!((s0.run_state)(Handle { written: vec![] }), (11, Handle { written: vec![] }));
+
+        let s1 = state_apply(
+            state_unit(Box::new(|h: Handle| h.written.len()) as Box<dyn FnOnce(Handle) -> usize>),
+            replace(Handle { written: vec![] })
+        );
+        assert_eq!((s1.run_state)(Handle { written: vec![1, 2] }), (2, Handle { written: vec![] }));
+    }
+
+    #[test]
+    fn test_bind() {
+        let s0 = state_bind(write(b"ab"), StateKleisli { kleisli: Box::new(|n: usize| {
+            state_fmap(move |m: usize| n + m, write(b"cde"))
+        }) });
+        assert_eq!((s0.run_state)(Handle { written: vec![] }), (5, Handle { written: b"abcde".to_vec() }));
+    }
+
+    #[test]
+    fn test_do() {
+        let session: StateMonad<Handle, Handle> = state_do!(once =>
+            n <- write(b"hello"),
+            len <- gets(|h: &Handle| h.written.len()),
+            modify(move |mut h: Handle| { h.written.push((n + len) as u8); h }),
+            replace(Handle { written: vec![] })
+        );
+        let (old, new): (Handle, Handle) = (session.run_state)(Handle { written: vec![] });
+        assert_eq!(old.written, b"hello\x0a".to_vec());
+        assert_eq!(new.written, vec![]);
+    }
+}
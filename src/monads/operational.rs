@@ -0,0 +1,410 @@
+use core::marker::PhantomData;
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+use super::instr::{Answer, Instr};
+#[cfg(feature = "state")]
+use super::state::StateMonad;
+#[cfg(feature = "reader")]
+use super::reader::ReaderMonad;
+#[cfg(feature = "writer")]
+use super::monoid::Monoid;
+#[cfg(feature = "writer")]
+use super::writer::{Writer, WriterMonad};
+
+
+// The operational (freer) monad.
+// Instructions are `Instr` types gathered in a plain enum `I`, with no
+// functor instance to write: a program is an instruction followed by a
+// queue of continuations, and `perform` gives it the type of answer its
+// instruction says. a handler turns each instruction into a computation in
+// the target monad, and `run_with` drives the program with a loop, so
+// neither deeply nested binds nor long programs grow the native stack.
+
+
+// where a program starts: a value, or an instruction to wait on.
+enum Head<I> {
+    Pure(Rc<dyn Fn() -> Answer>),
+    Instr(I)
+}
+
+impl<I: Clone> Clone for Head<I> {
+    fn clone(&self) -> Self {
+        match self {
+            Head::Pure(a) => Head::Pure(a.clone()),
+            Head::Instr(op) => Head::Instr(op.clone())
+        }
+    }
+}
+
+// what a continuation does with an answer: produce the next one right
+// away (`fmap`), or hand over to another program (`bind`).
+enum Next<I> {
+    Value(Answer),
+    Program(Head<I>, Vec<Cont<I>>)
+}
+
+type Cont<I> = Rc<dyn Fn(Answer) -> Next<I>>;
+
+
+pub struct Program<I, Ta> {
+    head: Head<I>,
+    conts: Vec<Cont<I>>,
+    result: PhantomData<fn() -> Ta>
+}
+
+impl<I: Clone, Ta> Clone for Program<I, Ta> {
+    fn clone(&self) -> Self { Program { head: self.head.clone(), conts: self.conts.clone(), result: PhantomData } }
+}
+
+
+pub fn program_unit<I, Ta: 'static + Clone>(a: Ta) -> Program<I, Ta> {
+    Program { head: Head::Pure(Rc::new(move || -> Answer { Answer::new(a.clone()) })), conts: Vec::new(), result: PhantomData }
+}
+
+
+// singleton :: instr a -> Program instr a
+// a program made of the single instruction `op`, giving its answer.
+pub fn perform<I, Op: Instr + Into<I>>(op: Op) -> Program<I, Op::Output> {
+    Program { head: Head::Instr(op.into()), conts: Vec::new(), result: PhantomData }
+}
+
+
+// functor
+pub fn program_fmap<I, Ta: 'static, Tb: 'static>(
+    f_ab: Rc<dyn Fn(Ta) -> Tb>,
+    ma: Program<I, Ta>
+) -> Program<I, Tb> {
+    let mut conts: Vec<Cont<I>> = ma.conts;
+    conts.push(Rc::new(move |x: Answer| -> Next<I> { Next::Value(Answer::new(f_ab(x.take::<Ta>()))) }));
+    Program { head: ma.head, conts, result: PhantomData }
+}
+
+
+// monad
+pub struct ProgramKleisli<I, Ta, Tb> {
+    pub kleisli: Rc<dyn Fn(Ta) -> Program<I, Tb>>
+}
+
+impl<I, Ta, Tb> Clone for ProgramKleisli<I, Ta, Tb> {
+    fn clone(&self) -> Self { ProgramKleisli { kleisli: self.kleisli.clone() } }
+}
+
+// the continuation joins the end of the queue; nothing is rebuilt, so
+// binds cost the same however they are nested.
+pub fn program_bind<I: 'static, Ta: 'static, Tb: 'static>(
+    ma: Program<I, Ta>,
+    k_ab: ProgramKleisli<I, Ta, Tb>
+) -> Program<I, Tb> {
+    let mut conts: Vec<Cont<I>> = ma.conts;
+    conts.push(Rc::new(move |x: Answer| -> Next<I> {
+        let mb: Program<I, Tb> = (k_ab.kleisli)(x.take::<Ta>());
+        Next::Program(mb.head, mb.conts)
+    }));
+    Program { head: ma.head, conts, result: PhantomData }
+}
+
+
+// applicative
+pub fn program_apply<I: Clone + 'static, Ta: 'static, Tb: 'static>(
+    mf: Program<I, Rc<dyn Fn(Ta) -> Tb>>,
+    ma: Program<I, Ta>
+) -> Program<I, Tb> {
+    program_bind(mf, ProgramKleisli { kleisli: Rc::new(move |f_ab: Rc<dyn Fn(Ta) -> Tb>| -> Program<I, Tb> { program_fmap(f_ab, ma.clone()) }) })
+}
+
+
+// the evaluation loop. `step` answers one instruction; continuations are
+// taken off the front of the queue and the ones a bind hands back go in
+// front of the rest, so the program runs in order and in constant stack.
+pub fn run_program<I, Ta: 'static>(ma: Program<I, Ta>, mut step: impl FnMut(I) -> Answer) -> Ta {
+    let mut conts: VecDeque<Cont<I>> = ma.conts.into();
+    let mut x: Answer = match ma.head {
+        Head::Pure(a) => a(),
+        Head::Instr(op) => step(op)
+    };
+    while let Some(k) = conts.pop_front() {
+        x = match k(x) {
+            Next::Value(y) => y,
+            Next::Program(head, next) => {
+                for k in next.into_iter().rev() {
+                    conts.push_front(k);
+                }
+                match head {
+                    Head::Pure(a) => a(),
+                    Head::Instr(op) => step(op)
+                }
+            }
+        };
+    }
+    x.take::<Ta>()
+}
+
+
+// Target monads for `run_with`. a handler answers each instruction with a
+// computation in one of them, and the whole program runs in the same one:
+// a handler `Fn(I) -> StateMonad<Ts, Answer>` makes a `StateMonad<Ts, Ta>`.
+pub trait Interpreter<I>: Sized {
+    type Run<Ta>;
+
+    fn interpret<Ta: 'static>(ma: Program<I, Ta>, handler: Rc<dyn Fn(I) -> Self>) -> Self::Run<Ta>;
+}
+
+impl<I: 'static, Ta> Program<I, Ta> {
+    pub fn run_with<M: Interpreter<I>>(self, handler: impl Fn(I) -> M + 'static) -> M::Run<Ta> where Ta: 'static {
+        M::interpret(self, Rc::new(handler))
+    }
+}
+
+// the state is threaded from one instruction to the next.
+#[cfg(feature = "state")]
+impl<I: Clone + 'static, Ts: 'static> Interpreter<I> for StateMonad<Ts, Answer> {
+    type Run<Ta> = StateMonad<Ts, Ta>;
+
+    fn interpret<Ta: 'static>(ma: Program<I, Ta>, handler: Rc<dyn Fn(I) -> Self>) -> StateMonad<Ts, Ta> {
+        StateMonad { run_state: Rc::new(move |s: Ts| -> (Ta, Ts) {
+            let mut s: Option<Ts> = Some(s);
+            let a: Ta = run_program(ma.clone(), |op: I| -> Answer {
+                let (x, next): (Answer, Ts) = (handler(op).run_state)(s.take().unwrap());
+                s = Some(next);
+                x
+            });
+            (a, s.take().unwrap())
+        }) }
+    }
+}
+
+// every instruction reads the same configuration.
+#[cfg(feature = "reader")]
+impl<I: Clone + 'static, Tcfg: 'static> Interpreter<I> for ReaderMonad<Tcfg, Answer> {
+    type Run<Ta> = ReaderMonad<Tcfg, Ta>;
+
+    fn interpret<Ta: 'static>(ma: Program<I, Ta>, handler: Rc<dyn Fn(I) -> Self>) -> ReaderMonad<Tcfg, Ta> {
+        ReaderMonad { run_reader: Rc::new(move |cfg: &Tcfg| -> Ta {
+            run_program(ma.clone(), |op: I| -> Answer { (handler(op).run_reader)(cfg) })
+        }) }
+    }
+}
+
+// the logs of the instructions are joined in order.
+#[cfg(feature = "writer")]
+impl<I: 'static, Tlog: Monoid<T = Tlog>> Interpreter<I> for Writer<Answer, Tlog> {
+    type Run<Ta> = WriterMonad<Ta, Tlog>;
+
+    fn interpret<Ta: 'static>(ma: Program<I, Ta>, handler: Rc<dyn Fn(I) -> Self>) -> WriterMonad<Ta, Tlog> {
+        let mut log: Tlog = Tlog::mempty();
+        let value: Ta = run_program(ma, |op: I| -> Answer {
+            let w: WriterMonad<Answer, Tlog> = handler(op);
+            log.mappend_assign(w.log);
+            w.value
+        });
+        Writer { value, log }
+    }
+}
+
+
+// do-notation. internally every rule carries the module to take the monad
+// from and the pointer its kleisli arrows are stored in.
+#[macro_export]
+macro_rules! program_do {
+    // trailing comma
+    (@[$($m:tt)*][$($p:tt)*]) => {};
+
+    (@[$($m:tt)*][$($p:tt)*] $v:ident = $e:expr,  $($rest:tt)*) => { (|$v| { $crate::program_do!(@[$($m)*][$($p)*] $($rest)*) })($e) };
+
+    (@[$($m:tt)*][$($p:tt)*] $v:ident <- $e:expr, $($rest:tt)*) => {
+        $($m)*::program_bind(
+            $e,
+            $($m)*::ProgramKleisli {
+                kleisli: $($p)*::new( move |$v| { $crate::program_do!(@[$($m)*][$($p)*] $($rest)*) } )
+            }
+        )
+    };
+
+    (@[$($m:tt)*][$($p:tt)*] $e:expr, $($rest:tt)*) => {
+        $($m)*::program_bind(
+            $e,
+            $($m)*::ProgramKleisli {
+                kleisli: $($p)*::new( move |_| { $crate::program_do!(@[$($m)*][$($p)*] $($rest)*) } )
+            }
+        )
+    };
+
+    (@[$($m:tt)*][$($p:tt)*] $e:expr) => { $e };
+
+    ($($rest:tt)*) => { $crate::program_do!(@[$crate::monads::operational][$crate::prelude::Rc] $($rest)*) };
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+
+    // a key-value store: an instruction type per operation, gathered in a plain enum.
+    #[derive(Clone)]
+    struct Get(String);
+
+    impl Instr for Get {
+        type Output = Option<String>;
+    }
+
+    #[derive(Clone)]
+    struct Put(String, String);
+
+    impl Instr for Put {
+        type Output = ();
+    }
+
+    #[derive(Clone)]
+    enum Kv {
+        Get(Get),
+        Put(Put)
+    }
+
+    impl From<Get> for Kv {
+        fn from(op: Get) -> Self { Kv::Get(op) }
+    }
+
+    impl From<Put> for Kv {
+        fn from(op: Put) -> Self { Kv::Put(op) }
+    }
+
+    fn get(key: &str) -> Program<Kv, Option<String>> { perform(Get(key.to_string())) }
+    fn put(key: &str, value: &str) -> Program<Kv, ()> { perform(Put(key.to_string(), value.to_string())) }
+
+    // appends a suffix to a value, if there is one.
+    fn touch(key: &'static str) -> Program<Kv, bool> {
+        program_do!(
+            value <- get(key),
+            match value {
+                Some(value) => program_do!(put(key, &(value + "!")), program_unit(true)),
+                None => program_unit(false)
+            }
+        )
+    }
+
+    // lists the instructions, answering every `Get` with nothing.
+    fn dry_run<Ta: 'static>(ma: Program<Kv, Ta>) -> (Ta, Vec<String>) {
+        let mut steps: Vec<String> = Vec::new();
+        let a: Ta = run_program(ma, |op: Kv| -> Answer {
+            match op {
+                Kv::Get(get) => { steps.push(get.0.clone()); get.answer(None) },
+                Kv::Put(put) => { steps.push(put.0.clone() + "=" + &put.1); put.answer(()) }
+            }
+        });
+        (a, steps)
+    }
+
+    #[test]
+    fn test_run() {
+        assert_eq!(dry_run(touch("a")), (false, vec!["a".to_string()]));
+
+        let m: Program<Kv, usize> = program_apply(
+            program_unit(Rc::new(|v: Option<String>| v.map_or(0, |v| v.len())) as Rc<dyn Fn(Option<String>) -> usize>),
+            program_fmap(Rc::new(|v: Option<String>| v.or(Some("abc".to_string()))), get("a"))
+        );
+        assert_eq!(dry_run(m), (3, vec!["a".to_string()]));
+    }
+
+    #[test]
+    #[cfg(feature = "state")]
+    fn test_state() {
+        use alloc::collections::BTreeMap;
+        type Store = BTreeMap<String, String>;
+
+        let handler = |op: Kv| -> StateMonad<Store, Answer> {
+            StateMonad { run_state: Rc::new(move |mut store: Store| -> (Answer, Store) {
+                match &op {
+                    Kv::Get(get) => (get.answer(store.get(&get.0).cloned()), store),
+                    Kv::Put(put) => { store.insert(put.0.clone(), put.1.clone()); (put.answer(()), store) }
+                }
+            }) }
+        };
+        let m: StateMonad<Store, bool> = touch("a").run_with(handler);
+        let store: Store = [("a".to_string(), "x".to_string())].into_iter().collect();
+        let touched: Store = [("a".to_string(), "x!".to_string())].into_iter().collect();
+        assert_eq!((m.run_state)(store), (true, touched));
+        assert_eq!((m.run_state)(Store::new()), (false, Store::new()));
+    }
+
+    #[test]
+    #[cfg(feature = "reader")]
+    fn test_reader() {
+        use core::cell::RefCell;
+        use alloc::collections::BTreeMap;
+        type Store = BTreeMap<String, String>;
+
+        let m: ReaderMonad<RefCell<Store>, bool> = touch("a").run_with(|op: Kv| -> ReaderMonad<RefCell<Store>, Answer> {
+            ReaderMonad { run_reader: Rc::new(move |db: &RefCell<Store>| -> Answer {
+                match &op {
+                    Kv::Get(get) => get.answer(db.borrow().get(&get.0).cloned()),
+                    Kv::Put(put) => { db.borrow_mut().insert(put.0.clone(), put.1.clone()); put.answer(()) }
+                }
+            }) }
+        });
+        let db: RefCell<Store> = RefCell::new([("a".to_string(), "x".to_string())].into_iter().collect());
+        assert!((m.run_reader)(&db));
+        assert!((m.run_reader)(&db));
+        assert_eq!(db.borrow().get("a"), Some(&"x!!".to_string()));
+    }
+
+    #[test]
+    #[cfg(feature = "writer")]
+    fn test_writer() {
+        use alloc::format;
+        use alloc::vec;
+
+        // an audit: records what would be done, reading `x` everywhere.
+        let w: WriterMonad<bool, Vec<String>> = touch("a").run_with(|op: Kv| -> WriterMonad<Answer, Vec<String>> {
+            match op {
+                Kv::Get(get) => Writer::new(get.answer(Some("x".to_string())), vec![format!("get {}", get.0)]),
+                Kv::Put(put) => Writer::new(put.answer(()), vec![format!("put {} {}", put.0, put.1)])
+            }
+        });
+        assert_eq!(w, Writer::new(true, vec!["get a".to_string(), "put a x!".to_string()]));
+    }
+
+    #[test]
+    #[should_panic(expected = "answered for another")]
+    fn test_answered_for_another() {
+        // answers a `Get` with what a `Put` is answered with: it compiles, but
+        // the program cannot take it as an `Option<String>`.
+        run_program(touch("a"), |op: Kv| -> Answer {
+            match op {
+                Kv::Get(_) => Put("a".to_string(), "b".to_string()).answer(()),
+                Kv::Put(put) => put.answer(())
+            }
+        });
+    }
+
+    #[test]
+    #[cfg(feature = "state")]
+    fn test_stack_safe() {
+        // an instruction set of one instruction.
+        #[derive(Clone)]
+        struct Tick;
+
+        impl Instr for Tick {
+            type Output = i64;
+        }
+
+        let tick = |op: Tick| -> StateMonad<i64, Answer> { StateMonad { run_state: Rc::new(move |n: i64| (op.answer(n), n + 1)) } };
+
+        // binds nested to the left.
+        let mut left: Program<Tick, i64> = program_unit(0);
+        for _ in 0..100_000 {
+            left = program_bind(left, ProgramKleisli { kleisli: Rc::new(|_: i64| perform(Tick)) });
+        }
+        assert_eq!((left.run_with(tick).run_state)(0), (99_999, 100_000));
+
+        // and to the right, built as it runs.
+        fn count(n: i64) -> Program<Tick, i64> {
+            if n == 0 { program_unit(0) } else { program_do!(_x <- perform(Tick), count(n - 1)) }
+        }
+        assert_eq!((count(100_000).run_with(tick).run_state)(0), (0, 100_000));
+    }
+}
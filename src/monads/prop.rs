@@ -0,0 +1,440 @@
+use core::fmt::{self, Debug, Display};
+use core::hash::{Hash, Hasher};
+use core::marker::PhantomData;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::{String, ToString};
+use alloc::vec;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+
+// A small property tester in the spirit of QuickCheck.
+// Inputs are drawn from a seeded generator so that every failure can be
+// replayed, and failing inputs are shrunk before they are reported.
+
+
+// splitmix64. tiny, fast, and good enough to drive test case generation.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng { Rng { state: seed } }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z: u64 = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // uniform in [0, n). n must be positive.
+    pub fn below(&mut self, n: u64) -> u64 { self.next_u64() % n }
+
+    // uniform in [lo, hi].
+    pub fn range(&mut self, lo: i64, hi: i64) -> i64 {
+        let span: u64 = hi.wrapping_sub(lo) as u64;
+        if span == u64::MAX { return self.next_u64() as i64 }
+        lo.wrapping_add(self.below(span + 1) as i64)
+    }
+
+    pub fn bool(&mut self) -> bool { self.next_u64() & 1 == 1 }
+}
+
+
+// A type whose values can be generated and shrunk.
+// `size` grows over the course of a run, so early cases are small.
+pub trait Arbitrary: Clone + Debug + Sized {
+    fn arbitrary(rng: &mut Rng, size: usize) -> Self;
+    fn shrink(&self) -> Vec<Self> { vec![] }
+}
+
+
+impl Arbitrary for () {
+    fn arbitrary(_: &mut Rng, _: usize) -> Self {}
+}
+
+impl Arbitrary for bool {
+    fn arbitrary(rng: &mut Rng, _: usize) -> Self { rng.bool() }
+    fn shrink(&self) -> Vec<Self> { if *self { vec![false] } else { vec![] } }
+}
+
+impl Arbitrary for i64 {
+    fn arbitrary(rng: &mut Rng, size: usize) -> Self {
+        // every so often step outside of the size bound to reach the extremes.
+        match rng.below(20) {
+            0 => [i64::MIN, i64::MAX, -1, 0, 1][rng.below(5) as usize],
+            _ => rng.range(-(size as i64), size as i64),
+        }
+    }
+    fn shrink(&self) -> Vec<Self> {
+        let x: i64 = *self;
+        if x == 0 { return vec![] }
+        let mut ret: Vec<i64> = vec![0];
+        if x / 2 != 0 { ret.push(x / 2); }
+        if x - x.signum() != 0 && x - x.signum() != x / 2 { ret.push(x - x.signum()); }
+        if x < 0 && x != i64::MIN { ret.push(-x); }
+        ret
+    }
+}
+
+impl Arbitrary for i32 {
+    fn arbitrary(rng: &mut Rng, size: usize) -> Self {
+        let bound: i64 = size.min(i32::MAX as usize) as i64;
+        rng.range(-bound, bound) as i32
+    }
+    fn shrink(&self) -> Vec<Self> {
+        (*self as i64).shrink().into_iter().map(|x| x as i32).collect()
+    }
+}
+
+impl Arbitrary for usize {
+    fn arbitrary(rng: &mut Rng, size: usize) -> Self { rng.below(size as u64 + 1) as usize }
+    fn shrink(&self) -> Vec<Self> {
+        match *self {
+            0 => vec![],
+            1 => vec![0],
+            x => vec![0, x / 2, x - 1],
+        }
+    }
+}
+
+impl Arbitrary for char {
+    fn arbitrary(rng: &mut Rng, _: usize) -> Self {
+        match rng.below(16) {
+            0 => '\n',
+            1 => ' ',
+            _ => (b'a' + rng.below(26) as u8) as char,
+        }
+    }
+    fn shrink(&self) -> Vec<Self> { if *self == 'a' { vec![] } else { vec!['a'] } }
+}
+
+impl Arbitrary for String {
+    fn arbitrary(rng: &mut Rng, size: usize) -> Self {
+        <Vec<char> as Arbitrary>::arbitrary(rng, size).into_iter().collect()
+    }
+    fn shrink(&self) -> Vec<Self> {
+        let chars: Vec<char> = self.chars().collect();
+        chars.shrink().into_iter().map(|cs| cs.into_iter().collect()).collect()
+    }
+}
+
+impl<A: Arbitrary> Arbitrary for Option<A> {
+    fn arbitrary(rng: &mut Rng, size: usize) -> Self {
+        if rng.below(4) == 0 { None } else { Some(A::arbitrary(rng, size)) }
+    }
+    fn shrink(&self) -> Vec<Self> {
+        match self {
+            None => vec![],
+            Some(a) => {
+                let mut ret: Vec<Self> = vec![None];
+                ret.extend(a.shrink().into_iter().map(Some));
+                ret
+            }
+        }
+    }
+}
+
+impl<A: Arbitrary> Arbitrary for Vec<A> {
+    fn arbitrary(rng: &mut Rng, size: usize) -> Self {
+        let len: usize = rng.below(size as u64 + 1) as usize;
+        (0..len).map(|_| A::arbitrary(rng, size)).collect()
+    }
+    fn shrink(&self) -> Vec<Self> {
+        let mut ret: Vec<Self> = vec![];
+        if self.is_empty() { return ret }
+        ret.push(vec![]);
+        // drop each half, then each element, then shrink elements in place.
+        let half: usize = self.len() / 2;
+        if half > 0 {
+            ret.push(self[half..].to_vec());
+            ret.push(self[..half].to_vec());
+        }
+        for i in 0..self.len() {
+            let mut v: Self = self.clone();
+            v.remove(i);
+            ret.push(v);
+        }
+        for (i, el) in self.iter().enumerate() {
+            for s in el.shrink() {
+                let mut v: Self = self.clone();
+                v[i] = s;
+                ret.push(v);
+            }
+        }
+        ret
+    }
+}
+
+impl<A: Arbitrary, B: Arbitrary> Arbitrary for (A, B) {
+    fn arbitrary(rng: &mut Rng, size: usize) -> Self {
+        (A::arbitrary(rng, size), B::arbitrary(rng, size))
+    }
+    fn shrink(&self) -> Vec<Self> {
+        let mut ret: Vec<Self> = vec![];
+        for a in self.0.shrink() { ret.push((a, self.1.clone())); }
+        for b in self.1.shrink() { ret.push((self.0.clone(), b)); }
+        ret
+    }
+}
+
+impl<A: Arbitrary, B: Arbitrary, C: Arbitrary> Arbitrary for (A, B, C) {
+    fn arbitrary(rng: &mut Rng, size: usize) -> Self {
+        (A::arbitrary(rng, size), B::arbitrary(rng, size), C::arbitrary(rng, size))
+    }
+    fn shrink(&self) -> Vec<Self> {
+        let mut ret: Vec<Self> = vec![];
+        for a in self.0.shrink() { ret.push((a, self.1.clone(), self.2.clone())); }
+        for b in self.1.shrink() { ret.push((self.0.clone(), b, self.2.clone())); }
+        for c in self.2.shrink() { ret.push((self.0.clone(), self.1.clone(), c)); }
+        ret
+    }
+}
+
+impl<A: Arbitrary, B: Arbitrary, C: Arbitrary, D: Arbitrary> Arbitrary for (A, B, C, D) {
+    fn arbitrary(rng: &mut Rng, size: usize) -> Self {
+        (A::arbitrary(rng, size), B::arbitrary(rng, size), C::arbitrary(rng, size), D::arbitrary(rng, size))
+    }
+    fn shrink(&self) -> Vec<Self> {
+        let mut ret: Vec<Self> = vec![];
+        for a in self.0.shrink() { ret.push((a, self.1.clone(), self.2.clone(), self.3.clone())); }
+        for b in self.1.shrink() { ret.push((self.0.clone(), b, self.2.clone(), self.3.clone())); }
+        for c in self.2.shrink() { ret.push((self.0.clone(), self.1.clone(), c, self.3.clone())); }
+        for d in self.3.shrink() { ret.push((self.0.clone(), self.1.clone(), self.2.clone(), d)); }
+        ret
+    }
+}
+
+// maps are generated and shrunk through their list of entries.
+impl<K: Arbitrary + Ord, V: Arbitrary> Arbitrary for BTreeMap<K, V> {
+    fn arbitrary(rng: &mut Rng, size: usize) -> Self {
+        <Vec<(K, V)> as Arbitrary>::arbitrary(rng, size).into_iter().collect()
+    }
+    fn shrink(&self) -> Vec<Self> {
+        let entries: Vec<(K, V)> = self.clone().into_iter().collect();
+        entries.shrink().into_iter().map(|es| es.into_iter().collect()).collect()
+    }
+}
+
+#[cfg(feature = "std")]
+impl<K: Arbitrary + Eq + Hash, V: Arbitrary> Arbitrary for HashMap<K, V> {
+    fn arbitrary(rng: &mut Rng, size: usize) -> Self {
+        <Vec<(K, V)> as Arbitrary>::arbitrary(rng, size).into_iter().collect()
+    }
+    fn shrink(&self) -> Vec<Self> {
+        let entries: Vec<(K, V)> = self.clone().into_iter().collect();
+        entries.shrink().into_iter().map(|es| es.into_iter().collect()).collect()
+    }
+}
+
+
+// FNV-1a, used to route a function argument to an entry of its table.
+struct Fnv(u64);
+
+impl Hasher for Fnv {
+    fn finish(&self) -> u64 { self.0 }
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x0100_0000_01b3);
+        }
+    }
+}
+
+
+// An arbitrary function a -> b.
+// It is a table of outputs indexed by the hash of the argument, which is
+// enough to generate, print and shrink functions like any other value.
+pub struct Fun<A, B> {
+    table: Vec<B>,
+    default: B,
+    arg: PhantomData<fn(A)>
+}
+
+impl<A: Hash, B: Clone> Fun<A, B> {
+    pub fn apply(&self, a: &A) -> B {
+        if self.table.is_empty() { return self.default.clone() }
+        let mut h: Fnv = Fnv(0xcbf2_9ce4_8422_2325);
+        a.hash(&mut h);
+        self.table[(h.finish() % self.table.len() as u64) as usize].clone()
+    }
+}
+
+impl<A: Hash + 'static, B: Clone + 'static> Fun<A, B> {
+    pub fn to_rc(&self) -> Rc<dyn Fn(A) -> B> {
+        let f: Fun<A, B> = self.clone();
+        Rc::new(move |a: A| -> B { f.apply(&a) })
+    }
+}
+
+impl<A, B: Clone> Clone for Fun<A, B> {
+    fn clone(&self) -> Self {
+        Fun { table: self.table.clone(), default: self.default.clone(), arg: PhantomData }
+    }
+}
+
+impl<A, B: Debug> Debug for Fun<A, B> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Fun {{ table: {:?}, default: {:?} }}", self.table, self.default)
+    }
+}
+
+impl<A, B: Arbitrary> Arbitrary for Fun<A, B> {
+    fn arbitrary(rng: &mut Rng, size: usize) -> Self {
+        Fun { table: Vec::arbitrary(rng, size), default: B::arbitrary(rng, size), arg: PhantomData }
+    }
+    fn shrink(&self) -> Vec<Self> {
+        let mut ret: Vec<Self> = vec![];
+        for t in self.table.shrink() {
+            ret.push(Fun { table: t, default: self.default.clone(), arg: PhantomData });
+        }
+        for d in self.default.shrink() {
+            ret.push(Fun { table: self.table.clone(), default: d, arg: PhantomData });
+        }
+        ret
+    }
+}
+
+
+#[derive(Debug, Clone, Copy)]
+pub struct Config {
+    pub seed: u64,
+    pub cases: usize,
+    pub max_size: usize,
+    pub max_shrinks: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self { Config { seed: 0x5eed, cases: 100, max_size: 32, max_shrinks: 1000 } }
+}
+
+
+// A failed property, with the smallest failing input found.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Counterexample {
+    pub property: String,
+    pub input: String,
+    pub reason: String,
+    pub case: usize,
+    pub shrinks: usize,
+    pub seed: u64,
+}
+
+impl Display for Counterexample {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f, "property `{}` failed on case {} (seed {:#x}, {} shrinks)\ninput: {}\n{}",
+            self.property, self.case, self.seed, self.shrinks, self.input, self.reason
+        )
+    }
+}
+
+
+// the outcome of a property on a single input; the error explains the failure.
+pub type Verdict = Result<(), String>;
+
+pub fn expect_eq<O: PartialEq + Debug>(lhs: O, rhs: O) -> Verdict {
+    if lhs == rhs { Ok(()) } else { Err(format!("lhs: {:?}\nrhs: {:?}", lhs, rhs)) }
+}
+
+
+// runs `prop` against `cfg.cases` generated inputs.
+// the first failure is shrunk greedily: any smaller input that still fails
+// replaces it, until no shrink fails or the shrink budget is spent.
+pub fn forall<T: Arbitrary>(
+    cfg: &Config,
+    name: &str,
+    prop: impl Fn(&T) -> Verdict
+) -> Result<(), Counterexample> {
+    let mut rng: Rng = Rng::new(cfg.seed);
+
+    for case in 0..cfg.cases {
+        let size: usize = 1 + case * cfg.max_size / cfg.cases.max(1);
+        let input: T = T::arbitrary(&mut rng, size);
+
+        if let Err(reason) = prop(&input) {
+            let mut smallest: (T, String) = (input, reason);
+            let mut shrinks: usize = 0;
+
+            'shrinking: while shrinks < cfg.max_shrinks {
+                for candidate in smallest.0.shrink() {
+                    if let Err(reason) = prop(&candidate) {
+                        smallest = (candidate, reason);
+                        shrinks += 1;
+                        continue 'shrinking;
+                    }
+                }
+                break;
+            }
+
+            return Err(Counterexample {
+                property: name.to_string(),
+                input: format!("{:?}", smallest.0),
+                reason: smallest.1,
+                case,
+                shrinks,
+                seed: cfg.seed,
+            })
+        }
+    }
+    Ok(())
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rng() {
+        let xs: Vec<u64> = { let mut rng = Rng::new(7); (0..10).map(|_| rng.next_u64()).collect() };
+        let ys: Vec<u64> = { let mut rng = Rng::new(7); (0..10).map(|_| rng.next_u64()).collect() };
+        assert_eq!(xs, ys);
+
+        let mut rng = Rng::new(1);
+        for _ in 0..1000 {
+            let x = rng.range(-3, 3);
+            assert!((-3..=3).contains(&x));
+        }
+    }
+
+    #[test]
+    fn test_fun() {
+        let mut rng = Rng::new(3);
+        let f: Fun<i64, i64> = Fun::arbitrary(&mut rng, 10);
+        for x in -20..20 {
+            assert_eq!(f.apply(&x), f.apply(&x));
+            assert_eq!(f.apply(&x), (f.to_rc())(x));
+        }
+    }
+
+    #[test]
+    fn test_forall() {
+        assert_eq!(forall(&Config::default(), "reverse twice", |xs: &Vec<i64>| {
+            let mut ys = xs.clone();
+            ys.reverse();
+            ys.reverse();
+            expect_eq(&ys, xs)
+        }), Ok(()));
+    }
+
+    #[test]
+    fn test_shrink() {
+        let failure = forall(&Config::default(), "small", |x: &i64| {
+            if *x < 10 { Ok(()) } else { Err("too big".to_string()) }
+        }).unwrap_err();
+        assert_eq!(failure.input, "10");
+
+        let failure = forall(&Config::default(), "no long lists", |xs: &Vec<bool>| {
+            if xs.len() < 3 { Ok(()) } else { Err("too long".to_string()) }
+        }).unwrap_err();
+        assert_eq!(failure.input, "[false, false, false]");
+    }
+}
@@ -1,13 +1,39 @@
-use std::rc::Rc;
+#[cfg(any(test, feature = "testing"))]
+use core::fmt::Debug;
+use alloc::rc::Rc;
 
+use super::identity::{Identity, Monad};
+#[cfg(any(test, feature = "testing"))]
+use super::observe::ObserveEq;
+#[cfg(any(test, feature = "testing"))]
+use super::prop::Arbitrary;
 
-pub struct ReaderMonad<Tcfg, Ta> {
-    pub run_reader: Rc<dyn Fn(Tcfg) -> Ta>
+
+// the configuration is lent to the computation rather than handed over,
+// so it is never copied or cloned, however many binds read from it.
+pub type RunReaderT<Tcfg, M, Ta> = Rc<dyn Fn(&Tcfg) -> <M as Monad>::M<Ta>>;
+
+
+// ReaderT r m a: computations in the base monad `M` that read a configuration.
+pub struct ReaderT<Tcfg, M: Monad, Ta> {
+    pub run_reader: RunReaderT<Tcfg, M, Ta>
+}
+
+// over `Identity` the base adds nothing: `run_reader` returns the value itself.
+pub type ReaderMonad<Tcfg, Ta> = ReaderT<Tcfg, Identity, Ta>;
+
+
+// reader monads are observed by running them on an arbitrary configuration.
+#[cfg(any(test, feature = "testing"))]
+impl<Tcfg: Arbitrary, Ta: PartialEq + Debug> ObserveEq for ReaderMonad<Tcfg, Ta> {
+    type Input = Tcfg;
+    type Output = Ta;
+    fn observe(&self, cfg: &Tcfg) -> Self::Output { (self.run_reader)(cfg) }
 }
 
 
 pub fn reader_unit<Tcfg, Ta: 'static + Clone>(a: Ta) -> ReaderMonad<Tcfg, Ta> {
-    ReaderMonad { run_reader: Rc::new(move |cfg: Tcfg| -> Ta { a.clone() } ) }
+    ReaderMonad { run_reader: Rc::new(move |_: &Tcfg| -> Ta { a.clone() } ) }
 }
 
 
@@ -16,102 +42,198 @@ pub fn reader_fmap<Tcfg: 'static, Ta: 'static, Tb: 'static>(
     f_ab: Rc<dyn Fn(Ta) -> Tb>,
     ma: ReaderMonad<Tcfg, Ta>
 ) -> ReaderMonad<Tcfg, Tb> {
-    ReaderMonad { run_reader: Rc::new( move |cfg: Tcfg| -> Tb { f_ab((ma.run_reader)(cfg)) } ) }
+    ReaderMonad { run_reader: Rc::new( move |cfg: &Tcfg| -> Tb { f_ab((ma.run_reader)(cfg)) } ) }
 }
 
 
 // applicative
-pub fn reader_apply<Tcfg: 'static + Copy, Ta: 'static, Tb: 'static>(
+pub fn reader_apply<Tcfg: 'static, Ta: 'static, Tb: 'static>(
     mf: ReaderMonad<Tcfg, Rc<dyn Fn(Ta) -> Tb>>,
     ma: ReaderMonad<Tcfg, Ta>
 ) -> ReaderMonad<Tcfg, Tb> {
-    ReaderMonad { run_reader: Rc::new( move |cfg: Tcfg| -> Tb { ((mf.run_reader)(cfg))((ma.run_reader)(cfg)) } ) }
+    ReaderMonad { run_reader: Rc::new( move |cfg: &Tcfg| -> Tb { ((mf.run_reader)(cfg))((ma.run_reader)(cfg)) } ) }
 }
 
 
 // monad
-pub struct ReaderKleisli<Tcfg, Ta, Tb> {
-    pub kleisli: Rc<dyn Fn(Ta) -> ReaderMonad<Tcfg, Tb>>
+pub struct ReaderTKleisli<Tcfg, M: Monad, Ta, Tb> {
+    pub kleisli: Rc<dyn Fn(Ta) -> ReaderT<Tcfg, M, Tb>>
 }
 
-pub fn reader_bind<Tcfg: 'static + Clone, Ta: 'static, Tb: 'static>(
+pub type ReaderKleisli<Tcfg, Ta, Tb> = ReaderTKleisli<Tcfg, Identity, Ta, Tb>;
+
+pub fn reader_bind<Tcfg: 'static, Ta: 'static, Tb: 'static>(
     ma: ReaderMonad<Tcfg, Ta>,
     k_ab: ReaderKleisli<Tcfg, Ta, Tb>
 ) -> ReaderMonad<Tcfg, Tb> {
-    ReaderMonad { run_reader: Rc::new( move |cfg: Tcfg| -> Tb { ((k_ab.kleisli)((ma.run_reader)(cfg.clone())).run_reader)(cfg.clone()) } ) }
+    ReaderMonad { run_reader: Rc::new( move |cfg: &Tcfg| -> Tb { ((k_ab.kleisli)((ma.run_reader)(cfg)).run_reader)(cfg) } ) }
+}
+
+
+// extracts a copy of the configuration from the monadic context to be used.
+pub fn load<Tcfg: Clone>() -> ReaderMonad<Tcfg, Tcfg> {
+    ReaderMonad { run_reader: Rc::new(|cfg: &Tcfg| -> Tcfg { cfg.clone() } ) }
+}
+
+
+// extracts only what is needed from the configuration, without copying the rest.
+pub fn asks<Tcfg, Ta>(f: impl Fn(&Tcfg) -> Ta + 'static) -> ReaderMonad<Tcfg, Ta> {
+    ReaderMonad { run_reader: Rc::new(f) }
 }
 
 
-// extracts the configuration from the monadic context to be used.
-pub fn load<Tcfg>() -> ReaderMonad<Tcfg, Tcfg> {
-    ReaderMonad { run_reader: Rc::new(|cfg: Tcfg| -> Tcfg { cfg } ) }
+// compatibility with readers written against an owned configuration.
+// the configuration is cloned once per call to `f`.
+pub fn reader_owned<Tcfg: Clone, Ta>(f: impl Fn(Tcfg) -> Ta + 'static) -> ReaderMonad<Tcfg, Ta> {
+    ReaderMonad { run_reader: Rc::new(move |cfg: &Tcfg| -> Ta { f(cfg.clone()) } ) }
+}
+
+// runs a reader on a configuration that is given away.
+pub fn run_reader<Tcfg, Ta>(ma: &ReaderMonad<Tcfg, Ta>, cfg: Tcfg) -> Ta {
+    (ma.run_reader)(&cfg)
 }
 
 
+// the same operations for any base monad. the plain ones above are kept
+// for `Identity`, where they need no trip through `Monad`.
+pub fn reader_t_unit<Tcfg, M: Monad, Ta: 'static + Clone>(a: Ta) -> ReaderT<Tcfg, M, Ta> {
+    ReaderT { run_reader: Rc::new(move |_: &Tcfg| -> M::M<Ta> { M::unit(a.clone()) } ) }
+}
+
+pub fn reader_t_bind<Tcfg: 'static, M: Monad + 'static, Ta: 'static, Tb: 'static>(
+    ma: ReaderT<Tcfg, M, Ta>,
+    k_ab: ReaderTKleisli<Tcfg, M, Ta, Tb>
+) -> ReaderT<Tcfg, M, Tb> {
+    ReaderT { run_reader: Rc::new( move |cfg: &Tcfg| -> M::M<Tb> {
+        M::bind((ma.run_reader)(cfg), |a: Ta| -> M::M<Tb> { ((k_ab.kleisli)(a).run_reader)(cfg) })
+    } ) }
+}
+
+// asks :: (r -> a) -> ReaderT r m a
+pub fn reader_t_asks<Tcfg, M: Monad, Ta>(f: impl Fn(&Tcfg) -> Ta + 'static) -> ReaderT<Tcfg, M, Ta> {
+    ReaderT { run_reader: Rc::new(move |cfg: &Tcfg| -> M::M<Ta> { M::unit(f(cfg)) } ) }
+}
+
+// lift :: m a -> ReaderT r m a
+pub fn reader_t_lift<Tcfg, M: Monad + 'static, Ta: 'static>(ma: M::M<Ta>) -> ReaderT<Tcfg, M, Ta>
+where
+    M::M<Ta>: Clone
+{
+    ReaderT { run_reader: Rc::new(move |_: &Tcfg| -> M::M<Ta> { ma.clone() } ) }
+}
+
+
+// do-notation. `reader_do!(sync => ...)` builds the thread-safe variant
+// from `monads::sync::reader` instead, and `reader_do!(once => ...)` the
+// single-shot one from `monads::once::reader`. internally every rule carries
+// the module to take the monad from and the pointer its kleisli arrows are
+// stored in.
 #[macro_export]
 macro_rules! reader_do {
+    (sync => $($rest:tt)*) => { $crate::reader_do!(@[$crate::monads::sync::reader][$crate::monads::sync::Arc] $($rest)*) };
+    (once => $($rest:tt)*) => { $crate::reader_do!(@[$crate::monads::once::reader][$crate::monads::once::Box] $($rest)*) };
+
     // trailing comma
-    () => {};
+    (@[$($m:tt)*][$($p:tt)*]) => {};
 
-    ($v:ident = $e:expr,  $($rest:tt)*) => { (|$v| { reader_do!($($rest)*) })($e) };
+    (@[$($m:tt)*][$($p:tt)*] $v:ident = $e:expr,  $($rest:tt)*) => { (|$v| { $crate::reader_do!(@[$($m)*][$($p)*] $($rest)*) })($e) };
 
-    ($v:ident <- $e:expr, $($rest:tt)*) => {
-        reader_bind(
+    (@[$($m:tt)*][$($p:tt)*] $v:ident <- $e:expr, $($rest:tt)*) => {
+        $($m)*::reader_bind(
             $e,
-            ReaderKleisli {
-                kleisli: Rc::new( move |$v| { reader_do!($($rest)*) } )
+            $($m)*::ReaderKleisli {
+                kleisli: $($p)*::new( move |$v| { $crate::reader_do!(@[$($m)*][$($p)*] $($rest)*) } )
             }
         )
     };
 
-    ($e:expr, $($rest:tt)*) => {
-        reader_bind(
+    (@[$($m:tt)*][$($p:tt)*] $e:expr, $($rest:tt)*) => {
+        $($m)*::reader_bind(
             $e,
-            ReaderKleisli {
-                kleisli: Rc::new( move |_| { reader_do!($($rest)*) } )
+            $($m)*::ReaderKleisli {
+                kleisli: $($p)*::new( move |_| { $crate::reader_do!(@[$($m)*][$($p)*] $($rest)*) } )
             }
         )
     };
 
-    ($e:expr) => { $e };
+    (@[$($m:tt)*][$($p:tt)*] $e:expr) => { $e };
+
+    ($($rest:tt)*) => { $crate::reader_do!(@[$crate::monads::reader][$crate::prelude::Rc] $($rest)*) };
 }
 
 
+
 // tests
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use alloc::format;
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use crate::monads::laws::{check_functor_laws, check_monad_laws};
+    use crate::monads::prop::{Config, Fun};
+    use crate::monads::identity::tests::Maybe;
+
+    fn gen_reader(f: &Fun<i64, i64>) -> ReaderMonad<i64, i64> {
+        let f: Fun<i64, i64> = f.clone();
+        ReaderMonad { run_reader: Rc::new(move |cfg| f.apply(cfg)) }
+    }
 
     #[test]
     fn test_fmap0() {
         let r0: ReaderMonad<&str, i64> = reader_fmap(
             Rc::new(|x: i64| 3*x),
-            ReaderMonad { run_reader: Rc::new(|cfg| 11) }
+            ReaderMonad { run_reader: Rc::new(|_| 11) }
         );
-        let r1: ReaderMonad<&str, i64> = ReaderMonad{ run_reader: Rc::new(|cfg| 33) };
-        assert_eq!((r0.run_reader)("hi"), (r1.run_reader)("hi"));
+        let r1: ReaderMonad<&str, i64> = ReaderMonad{ run_reader: Rc::new(|_| 33) };
+        assert_eq!((r0.run_reader)(&"hi"), (r1.run_reader)(&"hi"));
     }
 
     #[test]
     fn test_fmap1() {
         let r0: ReaderMonad<&str, i64> = reader_fmap(
             Rc::new(|x: i64| 3*x),
-            ReaderMonad { run_reader: Rc::new(|cfg| if cfg == "one" { 10 } else { 1 }) }
+            ReaderMonad { run_reader: Rc::new(|cfg| if *cfg == "one" { 10 } else { 1 }) }
         );
-        assert_eq!((r0.run_reader)("one"), 30);
-        assert_eq!((r0.run_reader)("none"), 3);
+        assert_eq!((r0.run_reader)(&"one"), 30);
+        assert_eq!((r0.run_reader)(&"none"), 3);
     }
 
     #[test]
     fn test_apply() {
         let r0: ReaderMonad<&str, Rc<dyn Fn(i64) -> i64>> = ReaderMonad {
-            run_reader: Rc::new( |cfg| {Rc::new(move |x| if cfg == "one" { x } else { 2 * x })})
+            run_reader: Rc::new( |cfg| { let cfg = *cfg; Rc::new(move |x| if cfg == "one" { x } else { 2 * x })})
         };
-        let r1: ReaderMonad<&str, i64> = ReaderMonad { run_reader: Rc::new( |cfg| 10 )};
+        let r1: ReaderMonad<&str, i64> = ReaderMonad { run_reader: Rc::new( |_| 10 )};
         let r2 = reader_apply(r0, r1);
-        assert_eq!((r2.run_reader)("one"), 10);
-        assert_eq!((r2.run_reader)("two"), 20);
+        assert_eq!((r2.run_reader)(&"one"), 10);
+        assert_eq!((r2.run_reader)(&"two"), 20);
+    }
+
+    #[test]
+    fn test_apply_borrowed() {
+        // a configuration that can be neither copied nor cloned.
+        struct Settings {
+            scale: Vec<i64>
+        }
+        let r0: ReaderMonad<Settings, Rc<dyn Fn(i64) -> i64>> = reader_fmap(
+            Rc::new(|n: i64| Rc::new(move |x: i64| n * x) as Rc<dyn Fn(i64) -> i64>),
+            asks(|cfg: &Settings| cfg.scale.iter().sum())
+        );
+        let r1: ReaderMonad<Settings, i64> = asks(|cfg: &Settings| cfg.scale.len() as i64);
+        let r2 = reader_apply(r0, r1);
+        assert_eq!(run_reader(&r2, Settings { scale: vec![1, 2, 3] }), 18);
+    }
+
+    #[test]
+    fn test_owned() {
+        let r0: ReaderMonad<String, usize> = reader_owned(|cfg: String| cfg.len());
+        let r1: ReaderMonad<String, usize> = reader_bind(r0, ReaderKleisli { kleisli: Rc::new(|n: usize| {
+            reader_owned(move |cfg: String| cfg.matches('a').count() + n)
+        }) });
+        assert_eq!(run_reader(&r1, "banana".to_string()), 9);
     }
 
     #[test]
@@ -122,10 +244,10 @@ mod tests {
                 ReaderMonad { run_reader: Rc::new( |cfg| if cfg.1 { (l, "t") } else { (l, "f") } ) }
             )};
         let r1 = reader_bind(r0, k0);
-        assert_eq!((r1.run_reader)((true, true)), ("t", "t"));
-        assert_eq!((r1.run_reader)((true, false)), ("t", "f"));
-        assert_eq!((r1.run_reader)((false, true)), ("f", "t"));
-        assert_eq!((r1.run_reader)((false, false)), ("f", "f"));
+        assert_eq!((r1.run_reader)(&(true, true)), ("t", "t"));
+        assert_eq!((r1.run_reader)(&(true, false)), ("t", "f"));
+        assert_eq!((r1.run_reader)(&(false, true)), ("f", "t"));
+        assert_eq!((r1.run_reader)(&(false, false)), ("f", "f"));
     }
 
     #[test]
@@ -141,8 +263,41 @@ mod tests {
             reader_unit(format!("{}-{}-{}", cfg.uname, cfg.host, cfg.ip))
         );
         assert_eq!(
-            (gen_login_str.run_reader)(User { uname: "a".to_string(), host: "b".to_string(), ip: "c".to_string() }),
+            (gen_login_str.run_reader)(&User { uname: "a".to_string(), host: "b".to_string(), ip: "c".to_string() }),
             "a-b-c".to_string()
         );
+
+        // the same without requiring `User: Clone`.
+        let gen_login_str: ReaderMonad<User, String> = reader_do!(
+            uname <- asks(|u: &User| u.uname.clone()),
+            host <- asks(|u: &User| u.host.clone()),
+            reader_unit(format!("{}-{}", uname, host))
+        );
+        assert_eq!(
+            run_reader(&gen_login_str, User { uname: "a".to_string(), host: "b".to_string(), ip: "c".to_string() }),
+            "a-b".to_string()
+        );
+    }
+
+    #[test]
+    fn test_transformer() {
+        // divides by the configuration, failing in the base monad on zero.
+        let divide: ReaderT<i64, Maybe, i64> = reader_t_bind(
+            reader_t_asks(|d: &i64| *d),
+            ReaderTKleisli { kleisli: Rc::new(|d: i64| if d == 0 { reader_t_lift(None) } else { reader_t_unit(100 / d) }) }
+        );
+        assert_eq!((divide.run_reader)(&4), Some(25));
+        assert_eq!((divide.run_reader)(&0), None);
+    }
+
+    #[test]
+    fn test_laws() {
+        check_functor_laws(&Config::default(), gen_reader, reader_fmap).unwrap();
+        check_monad_laws(
+            &Config::default(),
+            gen_reader,
+            reader_unit,
+            |m, k| reader_bind(m, ReaderKleisli { kleisli: k })
+        ).unwrap();
     }
 }
@@ -0,0 +1,312 @@
+use core::any::Any;
+use core::marker::PhantomData;
+use alloc::collections::VecDeque;
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+
+use super::cont::ContMonad;
+
+
+// A single-threaded scheduler for cooperative tasks.
+// a task is a continuation monad whose answer is what it asks of the
+// scheduler when it stops: to be put at the back of the queue, to start a
+// task, to wait for one, or to use a channel. the scheduler keeps the tasks'
+// results and the channels, so that handles to them are plain ids that
+// `cont_do!` can use at any step without cloning. tasks run round-robin, one
+// at a time up to their next stop, so a run is deterministic: the same tasks
+// always interleave the same way.
+pub type Task<Ta> = ContMonad<Action, Ta>;
+
+pub type Thunk = Rc<dyn Fn() -> Action>;
+
+// a result or a message, as the scheduler keeps it.
+pub type Value = Rc<dyn Any>;
+
+pub type Then<Ta> = Rc<dyn Fn(Ta) -> Action>;
+
+#[derive(Clone)]
+pub enum Action {
+    Done(Value),
+    Yield(Thunk),
+    // the new task, then the spawning task carrying on with its id.
+    Spawn(Thunk, Then<usize>),
+    Join(usize, Then<Value>),
+    Channel(Then<usize>),
+    Send(usize, Value, Thunk),
+    Recv(usize, Then<Value>)
+}
+
+
+// lets the other tasks run before carrying on.
+pub fn yield_now() -> Task<()> {
+    ContMonad { run_cont: Rc::new(|k: Then<()>| -> Action {
+        Action::Yield(Rc::new(move || -> Action { k(()) }))
+    }) }
+}
+
+
+// a task that was spawned, to `join`.
+pub struct JoinHandle<Ta> {
+    id: usize,
+    result: PhantomData<fn() -> Ta>
+}
+
+impl<Ta> Clone for JoinHandle<Ta> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<Ta> Copy for JoinHandle<Ta> {}
+
+// starts `task` at the back of the queue. the spawning task carries on.
+pub fn spawn<Ta: 'static>(task: Task<Ta>) -> Task<JoinHandle<Ta>> {
+    let child: Thunk = Rc::new(move || -> Action {
+        (task.run_cont)(Rc::new(|a: Ta| -> Action { Action::Done(Rc::new(a)) }))
+    });
+    ContMonad { run_cont: Rc::new(move |k: Then<JoinHandle<Ta>>| -> Action {
+        Action::Spawn(child.clone(), Rc::new(move |id: usize| -> Action { k(JoinHandle { id, result: PhantomData }) }))
+    }) }
+}
+
+// waits for the task of `handle` to finish, and gives its result.
+pub fn join<Ta: Clone + 'static>(handle: JoinHandle<Ta>) -> Task<Ta> {
+    ContMonad { run_cont: Rc::new(move |k: Then<Ta>| -> Action {
+        Action::Join(handle.id, Rc::new(move |v: Value| -> Action { k(downcast(v)) }))
+    }) }
+}
+
+
+// an unbounded first-in first-out channel between tasks.
+pub struct Channel<T> {
+    id: usize,
+    message: PhantomData<fn(T) -> T>
+}
+
+impl<T> Clone for Channel<T> {
+    fn clone(&self) -> Self { *self }
+}
+
+impl<T> Copy for Channel<T> {}
+
+pub fn channel<T: 'static>() -> Task<Channel<T>> {
+    ContMonad { run_cont: Rc::new(|k: Then<Channel<T>>| -> Action {
+        Action::Channel(Rc::new(move |id: usize| -> Action { k(Channel { id, message: PhantomData }) }))
+    }) }
+}
+
+// never waits, the channel being unbounded.
+pub fn send<T: 'static>(ch: Channel<T>, x: T) -> Task<()> {
+    let v: Value = Rc::new(x);
+    ContMonad { run_cont: Rc::new(move |k: Then<()>| -> Action {
+        Action::Send(ch.id, v.clone(), Rc::new(move || -> Action { k(()) }))
+    }) }
+}
+
+// waits for a message if there is none yet.
+pub fn recv<T: Clone + 'static>(ch: Channel<T>) -> Task<T> {
+    ContMonad { run_cont: Rc::new(move |k: Then<T>| -> Action {
+        Action::Recv(ch.id, Rc::new(move |v: Value| -> Action { k(downcast(v)) }))
+    }) }
+}
+
+fn downcast<Ta: Clone + 'static>(v: Value) -> Ta {
+    v.downcast_ref::<Ta>().expect("scheduler: value of the wrong type").clone()
+}
+
+
+// what the scheduler keeps for a channel: the messages not received yet,
+// or the tasks waiting for one.
+struct Queue {
+    messages: VecDeque<Value>,
+    receivers: VecDeque<(usize, Then<Value>)>
+}
+
+// runs `main` and the tasks it spawns until `main` finishes, and gives its
+// result. tasks still running then are dropped. `None` if every task is
+// left waiting on something that no task will do.
+pub fn run_tasks<Ta: 'static>(main: Task<Ta>) -> Option<Ta> {
+    let start: Thunk = Rc::new(move || -> Action {
+        (main.run_cont)(Rc::new(|a: Ta| -> Action { Action::Done(Rc::new(a)) }))
+    });
+
+    // task 0 is `main`.
+    let mut ready: VecDeque<(usize, Thunk)> = VecDeque::from([(0, start)]);
+    let mut results: Vec<Option<Value>> = Vec::from([None]);
+    let mut joining: Vec<Vec<(usize, Then<Value>)>> = Vec::from([Vec::new()]);
+    let mut channels: Vec<Queue> = Vec::new();
+
+    while let Some((id, task)) = ready.pop_front() {
+        let mut action: Action = task();
+        loop {
+            match action {
+                Action::Done(v) => {
+                    if id == 0 {
+                        return Rc::try_unwrap(v.downcast::<Ta>().ok()?).ok()
+                    }
+                    for (waiting, k) in joining[id].drain(..) {
+                        let v: Value = v.clone();
+                        ready.push_back((waiting, Rc::new(move || -> Action { k(v.clone()) })));
+                    }
+                    results[id] = Some(v);
+                    break
+                },
+                Action::Yield(rest) => {
+                    ready.push_back((id, rest));
+                    break
+                },
+                Action::Spawn(child, rest) => {
+                    let child_id: usize = results.len();
+                    results.push(None);
+                    joining.push(Vec::new());
+                    ready.push_back((child_id, child));
+                    action = rest(child_id)
+                },
+                Action::Join(other, rest) => match &results[other] {
+                    Some(v) => action = rest(v.clone()),
+                    None => {
+                        joining[other].push((id, rest));
+                        break
+                    }
+                },
+                Action::Channel(rest) => {
+                    channels.push(Queue { messages: VecDeque::new(), receivers: VecDeque::new() });
+                    action = rest(channels.len() - 1)
+                },
+                Action::Send(ch, v, rest) => {
+                    match channels[ch].receivers.pop_front() {
+                        Some((receiver, k)) => ready.push_back((receiver, Rc::new(move || -> Action { k(v.clone()) }))),
+                        None => channels[ch].messages.push_back(v)
+                    }
+                    action = rest()
+                },
+                Action::Recv(ch, rest) => match channels[ch].messages.pop_front() {
+                    Some(v) => action = rest(v),
+                    None => {
+                        channels[ch].receivers.push_back((id, rest));
+                        break
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::vec;
+    use crate::cont_do;
+    use crate::monads::cont::cont_unit;
+
+    type Log = Vec<(char, u32)>;
+
+    // logs `name` `n` times, yielding after each.
+    fn worker(log: Channel<(char, u32)>, name: char, n: u32) -> Task<u32> {
+        if n == 0 { return cont_unit(0) }
+        cont_do!(
+            send(log, (name, n)),
+            yield_now(),
+            rest <- worker(log, name, n - 1),
+            cont_unit(rest + n)
+        )
+    }
+
+    // the next `n` messages.
+    fn recv_many<T: Clone + 'static>(ch: Channel<T>, n: usize) -> Task<Vec<T>> {
+        if n == 0 { return cont_unit(Vec::new()) }
+        cont_do!(
+            x <- recv(ch),
+            rest <- recv_many(ch, n - 1),
+            cont_unit([vec![x.clone()], rest.clone()].concat())
+        )
+    }
+
+    #[test]
+    fn test_round_robin() {
+        let main: Task<(u32, u32, Log)> = cont_do!(
+            log <- channel(),
+            a <- spawn(worker(log, 'a', 3)),
+            b <- spawn(worker(log, 'b', 2)),
+            x <- join(a),
+            y <- join(b),
+            entries <- recv_many(log, 5),
+            cont_unit((x, y, entries.clone()))
+        );
+        let (x, y, entries): (u32, u32, Log) = run_tasks(main).unwrap();
+        assert_eq!((x, y), (6, 3));
+        assert_eq!(entries, vec![('a', 3), ('b', 2), ('a', 2), ('b', 1), ('a', 1)]);
+    }
+
+    fn producer(ch: Channel<u64>, from: u64, to: u64) -> Task<()> {
+        if from == to { return cont_unit(()) }
+        cont_do!(
+            send(ch, from),
+            yield_now(),
+            producer(ch, from + 1, to)
+        )
+    }
+
+    fn consumer(ch: Channel<u64>, n: u64, sum: u64) -> Task<u64> {
+        if n == 0 { return cont_unit(sum) }
+        cont_do!(
+            x <- recv(ch),
+            consumer(ch, n - 1, sum + x)
+        )
+    }
+
+    #[test]
+    fn test_channel() {
+        let main: Task<u64> = cont_do!(
+            ch <- channel(),
+            _p <- spawn(producer(ch, 0, 10_000)),
+            consumer(ch, 10_000, 0)
+        );
+        assert_eq!(run_tasks(main), Some(49_995_000));
+    }
+
+    // doubles what comes in on `input` onto `output`, `n` times.
+    fn doubler(input: Channel<u64>, output: Channel<u64>, n: usize) -> Task<()> {
+        if n == 0 { return cont_unit(()) }
+        cont_do!(
+            x <- recv(input),
+            send(output, 2 * x),
+            doubler(input, output, n - 1)
+        )
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let main: Task<Vec<u64>> = cont_do!(
+            input <- channel(),
+            middle <- channel(),
+            output <- channel(),
+            _d <- spawn(doubler(input, middle, 3)),
+            _e <- spawn(doubler(middle, output, 3)),
+            send(input, 1),
+            send(input, 5),
+            send(input, 7),
+            recv_many(output, 3)
+        );
+        assert_eq!(run_tasks(main), Some(vec![4, 20, 28]));
+    }
+
+    #[test]
+    fn test_deadlock() {
+        let main: Task<i32> = cont_do!(
+            ch <- channel(),
+            _t <- spawn(recv(ch)),
+            recv(ch)
+        );
+        assert_eq!(run_tasks(main), None);
+
+        // main does not wait for the tasks it spawned.
+        let main: Task<i32> = cont_do!(
+            ch <- channel::<i32>(),
+            _t <- spawn(recv(ch)),
+            cont_unit(7)
+        );
+        assert_eq!(run_tasks(main), Some(7));
+    }
+}
@@ -1,7 +1,33 @@
-use std::rc::Rc;
+#[cfg(any(test, feature = "testing"))]
+use core::fmt::Debug;
+use alloc::rc::Rc;
 
-pub struct StateMonad<Ts, Ta> {
-    pub run_state: Rc<dyn Fn(Ts) -> (Ta, Ts) >
+use super::identity::{Identity, Monad};
+#[cfg(any(test, feature = "testing"))]
+use super::observe::ObserveEq;
+#[cfg(any(test, feature = "testing"))]
+use super::prop::Arbitrary;
+
+
+// a step of the computation: from a state, the result and the next state in `M`.
+pub type RunStateT<Ts, M, Ta> = Rc<dyn Fn(Ts) -> <M as Monad>::M<(Ta, Ts)>>;
+
+
+// StateT s m a: a state threaded through computations in the base monad `M`.
+pub struct StateT<Ts, M: Monad, Ta> {
+    pub run_state: RunStateT<Ts, M, Ta>
+}
+
+// over `Identity` the base adds nothing: `run_state` returns the pair itself.
+pub type StateMonad<Ts, Ta> = StateT<Ts, Identity, Ta>;
+
+
+// state monads are observed by running them from an arbitrary initial state.
+#[cfg(any(test, feature = "testing"))]
+impl<Ts: Arbitrary + PartialEq, Ta: PartialEq + Debug> ObserveEq for StateMonad<Ts, Ta> {
+    type Input = Ts;
+    type Output = (Ta, Ts);
+    fn observe(&self, s: &Ts) -> Self::Output { (self.run_state)(s.clone()) }
 }
 
 
@@ -40,10 +66,12 @@ pub fn state_apply<Ts: 'static, Ta: 'static, Tb: 'static>(
 
 
 // monad
-pub struct StateKleisli<Ts, Ta, Tb> {
-    pub kleisli: Rc<dyn Fn(Ta) -> StateMonad<Ts, Tb>>
+pub struct StateTKleisli<Ts, M: Monad, Ta, Tb> {
+    pub kleisli: Rc<dyn Fn(Ta) -> StateT<Ts, M, Tb>>
 }
 
+pub type StateKleisli<Ts, Ta, Tb> = StateTKleisli<Ts, Identity, Ta, Tb>;
+
 pub fn state_bind<Ts: 'static, Ta: 'static, Tb: 'static>(
     ma: StateMonad<Ts, Ta>,
     k_ab: StateKleisli<Ts, Ta, Tb>
@@ -74,38 +102,94 @@ pub fn put<Ts: 'static + Clone>(s: Ts) -> StateMonad<Ts, ()> {
 }
 
 
+// the same operations for any base monad. the plain ones above are kept
+// for `Identity`, where they need no trip through `Monad`.
+pub fn state_t_unit<Ts: 'static, M: Monad + 'static, Ta: 'static + Clone>(a: Ta) -> StateT<Ts, M, Ta> {
+    StateT { run_state: Rc::new( move |s: Ts| -> M::M<(Ta, Ts)> { M::unit((a.clone(), s)) } ) }
+}
+
+pub fn state_t_bind<Ts: 'static, M: Monad + 'static, Ta: 'static, Tb: 'static>(
+    ma: StateT<Ts, M, Ta>,
+    k_ab: StateTKleisli<Ts, M, Ta, Tb>
+) -> StateT<Ts, M, Tb> {
+    StateT { run_state:
+        Rc::new( move |s: Ts| -> M::M<(Tb, Ts)> {
+            M::bind((ma.run_state)(s), |a_s: (Ta, Ts)| -> M::M<(Tb, Ts)> {
+                let mb: StateT<Ts, M, Tb> = (k_ab.kleisli)(a_s.0);
+                (mb.run_state)(a_s.1)
+            })
+        })
+    }
+}
+
+// state :: (s -> (a, s)) -> StateT s m a
+pub fn state_t<Ts, M: Monad, Ta>(f: impl Fn(Ts) -> (Ta, Ts) + 'static) -> StateT<Ts, M, Ta> {
+    StateT { run_state: Rc::new( move |s: Ts| -> M::M<(Ta, Ts)> { M::unit(f(s)) } ) }
+}
+
+// lift :: m a -> StateT s m a
+pub fn state_t_lift<Ts: 'static + Clone, M: Monad + 'static, Ta: 'static>(ma: M::M<Ta>) -> StateT<Ts, M, Ta>
+where
+    M::M<Ta>: Clone
+{
+    StateT { run_state: Rc::new( move |s: Ts| -> M::M<(Ta, Ts)> { M::fmap(|a: Ta| -> (Ta, Ts) { (a, s.clone()) }, ma.clone()) } ) }
+}
+
+
+// do-notation. `state_do!(sync => ...)` builds the thread-safe variant
+// from `monads::sync::state` instead, and `state_do!(once => ...)` the
+// single-shot one from `monads::once::state`. internally every rule carries
+// the module to take the monad from and the pointer its kleisli arrows are
+// stored in.
 #[macro_export]
 macro_rules! state_do {
-    () => {};
+    (sync => $($rest:tt)*) => { $crate::state_do!(@[$crate::monads::sync::state][$crate::monads::sync::Arc] $($rest)*) };
+    (once => $($rest:tt)*) => { $crate::state_do!(@[$crate::monads::once::state][$crate::monads::once::Box] $($rest)*) };
+
+    (@[$($m:tt)*][$($p:tt)*]) => {};
 
-    ($v:ident = $e:expr,  $($rest:tt)*) => { (|$v| { state_do!($($rest)*) })($e) };
+    (@[$($m:tt)*][$($p:tt)*] $v:ident = $e:expr,  $($rest:tt)*) => { (|$v| { $crate::state_do!(@[$($m)*][$($p)*] $($rest)*) })($e) };
 
-    ($v:ident <- $e:expr, $($rest:tt)*) => {
-        state_bind(
+    (@[$($m:tt)*][$($p:tt)*] $v:ident <- $e:expr, $($rest:tt)*) => {
+        $($m)*::state_bind(
             $e,
-            StateKleisli {
-                kleisli: Rc::new( move |$v| { state_do!($($rest)*) } )
+            $($m)*::StateKleisli {
+                kleisli: $($p)*::new( move |$v| { $crate::state_do!(@[$($m)*][$($p)*] $($rest)*) } )
             }
         )
     };
 
-    ($e:expr, $($rest:tt)*) => {
-        state_bind(
+    (@[$($m:tt)*][$($p:tt)*] $e:expr, $($rest:tt)*) => {
+        $($m)*::state_bind(
             $e,
-            StateKleisli {
-                kleisli: Rc::new( move |_| { state_do!($($rest)*) } )
+            $($m)*::StateKleisli {
+                kleisli: $($p)*::new( move |_| { $crate::state_do!(@[$($m)*][$($p)*] $($rest)*) } )
             }
         )
     };
 
-    ($e:expr) => { $e };
+    (@[$($m:tt)*][$($p:tt)*] $e:expr) => { $e };
+
+    ($($rest:tt)*) => { $crate::state_do!(@[$crate::monads::state][$crate::prelude::Rc] $($rest)*) };
 }
 
 
+
 // tests
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::monads::laws::{check_functor_laws, check_monad_laws};
+    use crate::monads::prop::{Config, Fun};
+    use crate::monads::identity::tests::Maybe;
+    use crate::monads::observe::observe_eq;
+
+    type Gen = (Fun<i64, i64>, Fun<i64, i64>);
+
+    fn gen_state(p: &Gen) -> StateMonad<i64, i64> {
+        let (f, g): Gen = p.clone();
+        StateMonad { run_state: Rc::new(move |s| (f.apply(&s), g.apply(&s))) }
+    }
 
     #[test]
     fn test_fmap() {
@@ -148,4 +232,38 @@ mod tests {
         );
         assert_eq!((run_game.run_state)((10, 13)), (0, (11, 13)));
     }
+
+    #[test]
+    fn test_transformer() {
+        // pops a counter, failing in the base monad once it is exhausted.
+        let pop = || -> StateT<i64, Maybe, i64> {
+            state_t_bind(state_t(|s: i64| (s, s)), StateTKleisli { kleisli: Rc::new(|n: i64| {
+                if n == 0 { state_t_lift(None) } else { state_t(move |_| (n, n - 1)) }
+            }) })
+        };
+        let pop_twice: StateT<i64, Maybe, i64> = state_t_bind(pop(), StateTKleisli { kleisli: Rc::new(move |a: i64| {
+            state_t_bind(pop(), StateTKleisli { kleisli: Rc::new(move |b: i64| state_t_unit(a + b)) })
+        }) });
+        assert_eq!((pop_twice.run_state)(5), Some((9, 3)));
+        assert_eq!((pop_twice.run_state)(1), None);
+
+        // over `Identity` the transformer forms are the plain monad.
+        let by_transformer: StateMonad<i64, i64> = state_t_bind(
+            state_t(|s: i64| (s, s.wrapping_mul(2))),
+            StateTKleisli { kleisli: Rc::new(|a: i64| state_t(move |s: i64| (a.wrapping_add(s), s))) }
+        );
+        let by_hand: StateMonad<i64, i64> = StateMonad { run_state: Rc::new(|s: i64| (s.wrapping_add(s.wrapping_mul(2)), s.wrapping_mul(2))) };
+        assert_eq!(observe_eq(&Config::default(), &by_transformer, &by_hand), Ok(()));
+    }
+
+    #[test]
+    fn test_laws() {
+        check_functor_laws(&Config::default(), gen_state, state_fmap).unwrap();
+        check_monad_laws(
+            &Config::default(),
+            gen_state,
+            state_unit,
+            |m, k| state_bind(m, StateKleisli { kleisli: k })
+        ).unwrap();
+    }
 }
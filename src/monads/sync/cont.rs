@@ -0,0 +1,145 @@
+use alloc::sync::Arc;
+#[cfg(any(test, feature = "testing"))]
+use core::hash::Hash;
+
+#[cfg(any(test, feature = "testing"))]
+use crate::monads::observe::ObserveEq;
+#[cfg(any(test, feature = "testing"))]
+use crate::monads::prop::{Arbitrary, Fun};
+
+
+// a suspended computation: given what to do with an `a`, it produces the final `r`.
+pub type RunCont<Tr, Ta> = Arc<dyn Fn(Arc<dyn Fn(Ta) -> Tr + Send + Sync>) -> Tr + Send + Sync>;
+
+
+pub struct ContMonad<Tr, Ta> {
+    pub run_cont: RunCont<Tr, Ta>
+}
+
+impl<Tr, Ta> Clone for ContMonad<Tr, Ta> {
+    fn clone(&self) -> Self { ContMonad { run_cont: self.run_cont.clone() } }
+}
+
+
+// the generated continuation is not thread-safe, so it is replayed through a
+// table of its outputs that is.
+#[cfg(any(test, feature = "testing"))]
+impl<Tr: Arbitrary + PartialEq + Send + Sync + 'static, Ta: Hash + 'static> ObserveEq for ContMonad<Tr, Ta> {
+    type Input = Fun<Ta, Tr>;
+    type Output = Tr;
+    fn observe(&self, k: &Fun<Ta, Tr>) -> Self::Output {
+        let k: Fun<Ta, Tr> = k.clone();
+        (self.run_cont)(Arc::new(move |a: Ta| -> Tr { k.apply(&a) }))
+    }
+}
+
+
+pub fn cont_unit<Tr, Ta: 'static + Clone + Send + Sync>(a: Ta) -> ContMonad<Tr, Ta>{
+    ContMonad { run_cont: Arc::new(
+        move |f: Arc<dyn Fn(Ta) -> Tr + Send + Sync>| -> Tr { f(a.clone()) }
+    ) }
+}
+
+
+pub fn cont_eval<Tr>(
+    ma: ContMonad<Tr, Tr>
+) -> Tr {
+    (ma.run_cont)(Arc::new( move |x| x))
+}
+
+
+// functor
+pub fn cont_fmap<Tr: 'static, Ta: 'static, Tb: 'static>(
+    f_ab: Arc<dyn Fn(Ta) -> Tb + Send + Sync>,
+    ma: ContMonad<Tr, Ta>
+) -> ContMonad<Tr, Tb> {
+    ContMonad { run_cont: Arc::new( move |f_br: Arc<dyn Fn(Tb) -> Tr + Send + Sync>| -> Tr {
+        let f_ab_clone: Arc<dyn Fn(Ta) -> Tb + Send + Sync> = f_ab.clone();
+        let f_ar: Arc<dyn Fn(Ta) -> Tr + Send + Sync> = Arc::new(move |a: Ta| -> Tr { f_br(f_ab_clone(a)) } );
+        (ma.run_cont)(f_ar)
+    } ) }
+}
+
+
+// applicative
+pub fn cont_apply<Tr: 'static, Ta: 'static, Tb: 'static>(
+    mf: ContMonad<Tr, Arc<dyn Fn(Ta) -> Tb + Send + Sync>>,
+    ma: ContMonad<Tr, Ta>
+) -> ContMonad<Tr, Tb> {
+    let f_abrr: RunCont<Tr, Arc<dyn Fn(Ta) -> Tb + Send + Sync>> = mf.run_cont.clone();
+
+    ContMonad { run_cont: Arc::new( move |f_br: Arc<dyn Fn(Tb) -> Tr + Send + Sync>| -> Tr {
+        let f_arr: RunCont<Tr, Ta> = ma.run_cont.clone();
+
+        f_abrr(Arc::new(move |f_ab: Arc<dyn Fn(Ta) -> Tb + Send + Sync>| -> Tr {
+            let f_br_clone: Arc<dyn Fn(Tb) -> Tr + Send + Sync> = f_br.clone();
+
+            f_arr(Arc::new( move |a: Ta| -> Tr {
+                f_br_clone(f_ab(a))
+            } ) )
+        } ) )
+    } ) }
+}
+
+
+// monad
+pub struct ContKleisli<Tr, Ta, Tb> {
+    pub kleisli: Arc<dyn Fn(Ta) -> ContMonad<Tr, Tb> + Send + Sync>
+}
+
+pub fn cont_bind<Tr: 'static, Ta: 'static, Tb: 'static>(
+    ma: ContMonad<Tr, Ta>,
+    k_ab: ContKleisli<Tr, Ta, Tb>
+) -> ContMonad<Tr, Tb> {
+    ContMonad { run_cont: Arc::new( move |f_br: Arc<dyn Fn(Tb) -> Tr + Send + Sync>| {
+        let f_a_mbrr: Arc<dyn Fn(Ta) -> ContMonad<Tr, Tb> + Send + Sync> = (k_ab.kleisli).clone();
+
+        let f_ar: Arc<dyn Fn(Ta) -> Tr + Send + Sync> = Arc::new( move |a: Ta| -> Tr {
+            let f_br_clone: Arc<dyn Fn(Tb) -> Tr + Send + Sync> = f_br.clone();
+            (f_a_mbrr(a).run_cont)(f_br_clone)
+        });
+        (ma.run_cont)(f_ar)
+    } ) }
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cont_do;
+    use crate::monads::observe::observe_eq;
+    use crate::monads::prop::Config;
+
+    #[test]
+    fn test_do() {
+        let calc: ContMonad<i64, i64> = cont_do!(sync =>
+            x <- cont_unit(10),
+            y <- cont_unit(4 * x),
+            z <- cont_unit(y + 11),
+            cont_unit(z)
+        );
+        assert_eq!(cont_eval(calc), 51);
+    }
+
+    #[test]
+    fn test_fmap_apply() {
+        let m0: ContMonad<i64, i64> = cont_apply(
+            cont_unit(Arc::new(|x: i64| x.wrapping_mul(2)) as Arc<dyn Fn(i64) -> i64 + Send + Sync>),
+            cont_unit(21)
+        );
+        let m1: ContMonad<i64, i64> = cont_fmap(Arc::new(|x: i64| x.wrapping_add(21)), cont_unit(21));
+        assert_eq!(observe_eq(&Config::default(), &m0, &m1), Ok(()));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_threads() {
+        let calc: ContMonad<i64, i64> = cont_do!(sync =>
+            x <- cont_unit(6),
+            cont_unit(x * 7)
+        );
+        let worker = std::thread::spawn(move || cont_eval(calc));
+        assert_eq!(worker.join().unwrap(), 42);
+    }
+}
@@ -0,0 +1,15 @@
+// Thread-safe variants of the monads.
+// They mirror their `Rc` based counterparts one for one, except that every
+// function is stored as `Arc<dyn Fn + Send + Sync>`, so a computation can be
+// built on one thread and run on another. In exchange, everything captured
+// along the way has to be `Send + Sync` as well.
+pub use alloc::sync::Arc;
+
+#[cfg(feature = "reader")]
+pub mod reader;
+#[cfg(feature = "state")]
+pub mod state;
+#[cfg(feature = "writer")]
+pub mod writer;
+#[cfg(feature = "cont")]
+pub mod cont;
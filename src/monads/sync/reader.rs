@@ -0,0 +1,131 @@
+use alloc::sync::Arc;
+#[cfg(any(test, feature = "testing"))]
+use core::fmt::Debug;
+
+#[cfg(any(test, feature = "testing"))]
+use crate::monads::observe::ObserveEq;
+#[cfg(any(test, feature = "testing"))]
+use crate::monads::prop::Arbitrary;
+
+
+pub struct ReaderMonad<Tcfg, Ta> {
+    pub run_reader: Arc<dyn Fn(&Tcfg) -> Ta + Send + Sync>
+}
+
+
+#[cfg(any(test, feature = "testing"))]
+impl<Tcfg: Arbitrary, Ta: PartialEq + Debug> ObserveEq for ReaderMonad<Tcfg, Ta> {
+    type Input = Tcfg;
+    type Output = Ta;
+    fn observe(&self, cfg: &Tcfg) -> Self::Output { (self.run_reader)(cfg) }
+}
+
+
+pub fn reader_unit<Tcfg, Ta: 'static + Clone + Send + Sync>(a: Ta) -> ReaderMonad<Tcfg, Ta> {
+    ReaderMonad { run_reader: Arc::new(move |_: &Tcfg| -> Ta { a.clone() } ) }
+}
+
+
+// functor
+pub fn reader_fmap<Tcfg: 'static, Ta: 'static, Tb: 'static>(
+    f_ab: Arc<dyn Fn(Ta) -> Tb + Send + Sync>,
+    ma: ReaderMonad<Tcfg, Ta>
+) -> ReaderMonad<Tcfg, Tb> {
+    ReaderMonad { run_reader: Arc::new( move |cfg: &Tcfg| -> Tb { f_ab((ma.run_reader)(cfg)) } ) }
+}
+
+
+// applicative
+pub fn reader_apply<Tcfg: 'static, Ta: 'static, Tb: 'static>(
+    mf: ReaderMonad<Tcfg, Arc<dyn Fn(Ta) -> Tb + Send + Sync>>,
+    ma: ReaderMonad<Tcfg, Ta>
+) -> ReaderMonad<Tcfg, Tb> {
+    ReaderMonad { run_reader: Arc::new( move |cfg: &Tcfg| -> Tb { ((mf.run_reader)(cfg))((ma.run_reader)(cfg)) } ) }
+}
+
+
+// monad
+pub struct ReaderKleisli<Tcfg, Ta, Tb> {
+    pub kleisli: Arc<dyn Fn(Ta) -> ReaderMonad<Tcfg, Tb> + Send + Sync>
+}
+
+pub fn reader_bind<Tcfg: 'static, Ta: 'static, Tb: 'static>(
+    ma: ReaderMonad<Tcfg, Ta>,
+    k_ab: ReaderKleisli<Tcfg, Ta, Tb>
+) -> ReaderMonad<Tcfg, Tb> {
+    ReaderMonad { run_reader: Arc::new( move |cfg: &Tcfg| -> Tb { ((k_ab.kleisli)((ma.run_reader)(cfg)).run_reader)(cfg) } ) }
+}
+
+
+// extracts a copy of the configuration from the monadic context to be used.
+pub fn load<Tcfg: Clone>() -> ReaderMonad<Tcfg, Tcfg> {
+    ReaderMonad { run_reader: Arc::new(|cfg: &Tcfg| -> Tcfg { cfg.clone() } ) }
+}
+
+
+// extracts only what is needed from the configuration, without copying the rest.
+pub fn asks<Tcfg, Ta>(f: impl Fn(&Tcfg) -> Ta + Send + Sync + 'static) -> ReaderMonad<Tcfg, Ta> {
+    ReaderMonad { run_reader: Arc::new(f) }
+}
+
+
+// compatibility with readers written against an owned configuration.
+// the configuration is cloned once per call to `f`.
+pub fn reader_owned<Tcfg: Clone, Ta>(f: impl Fn(Tcfg) -> Ta + Send + Sync + 'static) -> ReaderMonad<Tcfg, Ta> {
+    ReaderMonad { run_reader: Arc::new(move |cfg: &Tcfg| -> Ta { f(cfg.clone()) } ) }
+}
+
+// runs a reader on a configuration that is given away.
+pub fn run_reader<Tcfg, Ta>(ma: &ReaderMonad<Tcfg, Ta>, cfg: Tcfg) -> Ta {
+    (ma.run_reader)(&cfg)
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::string::{String, ToString};
+    use crate::reader_do;
+
+    #[test]
+    fn test_do() {
+        let greeting: ReaderMonad<(&'static str, i64), String> = reader_do!(sync =>
+            cfg <- load(),
+            n = cfg.1 + 1,
+            reader_unit(format!("{}-{}", cfg.0, n))
+        );
+        assert_eq!((greeting.run_reader)(&("a", 1)), "a-2".to_string());
+    }
+
+    #[test]
+    fn test_apply() {
+        let r0: ReaderMonad<i64, i64> = reader_apply(
+            reader_fmap(Arc::new(|cfg: i64| Arc::new(move |x: i64| x * cfg) as Arc<dyn Fn(i64) -> i64 + Send + Sync>), load()),
+            reader_unit(10)
+        );
+        assert_eq!((r0.run_reader)(&3), 30);
+    }
+
+    #[test]
+    fn test_asks() {
+        let r0: ReaderMonad<String, usize> = reader_do!(sync =>
+            n <- asks(|cfg: &String| cfg.len()),
+            m <- reader_owned(|cfg: String| cfg.matches('a').count()),
+            reader_unit(n + m)
+        );
+        assert_eq!(run_reader(&r0, "banana".to_string()), 9);
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_threads() {
+        let r0: ReaderMonad<i64, i64> = reader_do!(sync =>
+            cfg <- load(),
+            reader_unit(cfg * cfg)
+        );
+        let worker = std::thread::spawn(move || run_reader(&r0, 12));
+        assert_eq!(worker.join().unwrap(), 144);
+    }
+}
@@ -0,0 +1,145 @@
+use alloc::sync::Arc;
+#[cfg(any(test, feature = "testing"))]
+use core::fmt::Debug;
+
+#[cfg(any(test, feature = "testing"))]
+use crate::monads::observe::ObserveEq;
+#[cfg(any(test, feature = "testing"))]
+use crate::monads::prop::Arbitrary;
+
+
+pub struct StateMonad<Ts, Ta> {
+    pub run_state: Arc<dyn Fn(Ts) -> (Ta, Ts) + Send + Sync>
+}
+
+
+#[cfg(any(test, feature = "testing"))]
+impl<Ts: Arbitrary + PartialEq, Ta: PartialEq + Debug> ObserveEq for StateMonad<Ts, Ta> {
+    type Input = Ts;
+    type Output = (Ta, Ts);
+    fn observe(&self, s: &Ts) -> Self::Output { (self.run_state)(s.clone()) }
+}
+
+
+pub fn state_unit<Ts: 'static + Clone, Ta: 'static + Copy + Send + Sync>(a: Ta) -> StateMonad<Ts, Ta> {
+    StateMonad { run_state: Arc::new( move |s: Ts| -> (Ta, Ts) { (a, s.clone()) } ) }
+}
+
+
+// functor
+pub fn state_fmap<Ts: 'static, Ta: 'static, Tb: 'static>(
+    f_ab: Arc<dyn Fn(Ta) -> Tb + Send + Sync>,
+    ma: StateMonad<Ts, Ta>
+) -> StateMonad<Ts, Tb> {
+    StateMonad { run_state:
+        Arc::new( move |s: Ts| -> (Tb, Ts) {
+            let a_s: (Ta, Ts) = (ma.run_state)(s);
+            (f_ab(a_s.0), a_s.1)
+        })
+    }
+}
+
+
+// applicative
+pub fn state_apply<Ts: 'static, Ta: 'static, Tb: 'static>(
+    mf: StateMonad<Ts, Arc<dyn Fn(Ta) -> Tb + Send + Sync>>,
+    ma: StateMonad<Ts, Ta>
+) -> StateMonad<Ts, Tb> {
+    StateMonad { run_state:
+        Arc::new( move |s: Ts| -> (Tb, Ts) {
+            let f_s: (Arc<dyn Fn(Ta) -> Tb + Send + Sync>, Ts) = (mf.run_state)(s);
+            let a_s: (Ta, Ts) = (ma.run_state)(f_s.1);
+            ((f_s.0)(a_s.0), a_s.1)
+        })
+    }
+}
+
+
+// monad
+pub struct StateKleisli<Ts, Ta, Tb> {
+    pub kleisli: Arc<dyn Fn(Ta) -> StateMonad<Ts, Tb> + Send + Sync>
+}
+
+pub fn state_bind<Ts: 'static, Ta: 'static, Tb: 'static>(
+    ma: StateMonad<Ts, Ta>,
+    k_ab: StateKleisli<Ts, Ta, Tb>
+) -> StateMonad<Ts, Tb> {
+    StateMonad { run_state:
+        Arc::new( move |s: Ts| -> (Tb, Ts) {
+            let a_s: (Ta, Ts) = (ma.run_state)(s);
+            let mb: StateMonad<Ts, Tb> = (k_ab.kleisli)(a_s.0);
+            (mb.run_state)(a_s.1)
+        })
+    }
+}
+
+
+// extracts the state from the monadic context.
+pub fn get<Ts: Clone>() -> StateMonad<Ts, Ts> {
+    StateMonad { run_state: Arc::new( move |s: Ts| -> (Ts, Ts) {
+        let s0: Ts = s.clone();
+        let s1: Ts = s.clone();
+        (s0, s1)
+    }) }
+}
+
+
+// returns the state into the monadic context
+pub fn put<Ts: 'static + Clone + Send + Sync>(s: Ts) -> StateMonad<Ts, ()> {
+    StateMonad { run_state: Arc::new( move |_: Ts| -> ((), Ts) { ((), s.clone()) } ) }
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::monads::observe::observe_eq;
+    use crate::monads::prop::Config;
+    use crate::state_do;
+
+    #[test]
+    fn test_do() {
+        let run_game: StateMonad<(i64, i64), i64> = state_do!(sync =>
+            st <- get(),
+            winner = 0,
+            put(if winner == 0 { (st.0+1, st.1) } else { (st.0, st.1+1) }),
+            state_unit(winner)
+        );
+        assert_eq!((run_game.run_state)((10, 13)), (0, (11, 13)));
+    }
+
+    #[test]
+    fn test_fmap_apply() {
+        let s0: StateMonad<i64, i64> = state_apply(
+            StateMonad { run_state: Arc::new(|s| (Arc::new(move |x: i64| x.wrapping_add(s)) as Arc<dyn Fn(i64) -> i64 + Send + Sync>, s)) },
+            state_fmap(Arc::new(|x: i64| x.wrapping_mul(2)), get())
+        );
+        let s1: StateMonad<i64, i64> = StateMonad { run_state: Arc::new(|s: i64| (s.wrapping_mul(3), s)) };
+        assert_eq!((s0.run_state)(5), (15, 5));
+        assert_eq!(observe_eq(&Config::default(), &s0, &s1), Ok(()));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_threads() {
+        use alloc::vec;
+        use alloc::vec::Vec;
+
+        let count: StateMonad<Vec<i64>, usize> = state_do!(sync =>
+            xs <- get(),
+            put(xs.iter().map(|x| x * 2).collect::<Vec<i64>>()),
+            state_unit(xs.len())
+        );
+        let count: Arc<StateMonad<Vec<i64>, usize>> = Arc::new(count);
+
+        let workers: Vec<std::thread::JoinHandle<(usize, Vec<i64>)>> = (0..4)
+            .map(|i| {
+                let count = count.clone();
+                std::thread::spawn(move || (count.run_state)((0..i).collect()))
+            })
+            .collect();
+        let results: Vec<(usize, Vec<i64>)> = workers.into_iter().map(|w| w.join().unwrap()).collect();
+        assert_eq!(results, vec![(0, vec![]), (1, vec![0]), (2, vec![0, 2]), (3, vec![0, 2, 4])]);
+    }
+}
@@ -0,0 +1,81 @@
+use alloc::sync::Arc;
+
+use crate::monads::monoid::Monoid;
+
+// the writer monad itself holds no functions, only its kleisli arrows do.
+pub use crate::monads::writer::{Writer, WriterMonad, writer_unit, writer_fmap, writer_apply, log};
+
+
+// monad
+pub struct WriterKleisli<Ta, Tb, Tlog: Monoid> {
+    pub kleisli: Arc<dyn Fn(Ta) -> WriterMonad<Tb, Tlog> + Send + Sync>
+}
+
+impl<Ta, Tb, Tlog: Monoid> Clone for WriterKleisli<Ta, Tb, Tlog> {
+    fn clone(&self) -> Self { WriterKleisli { kleisli: self.kleisli.clone() } }
+}
+
+pub fn writer_bind<Ta, Tb, Tlog: Monoid<T = Tlog>>(
+    ma: WriterMonad<Ta, Tlog>,
+    k_ab: WriterKleisli<Ta, Tb, Tlog>
+) -> WriterMonad<Tb, Tlog> {
+    let b_log_ab: WriterMonad<Tb, Tlog> = (k_ab.kleisli)(ma.value);
+    let mut log: Tlog = ma.log;
+    log.mappend_assign(b_log_ab.log);
+    Writer { value: b_log_ab.value, log }
+}
+
+
+pub fn compose_writers<Ta: 'static, Tb: 'static, Tc: 'static, Tlog: Monoid<T = Tlog> + 'static>(
+    wab: WriterKleisli<Ta, Tb, Tlog>,
+    wbc: WriterKleisli<Tb, Tc, Tlog>
+) -> WriterKleisli<Ta, Tc, Tlog> {
+
+    WriterKleisli {
+        kleisli: Arc::new(move |a: Ta| -> WriterMonad<Tc, Tlog> {
+            let b_log_ab: WriterMonad<Tb, Tlog> = (wab.kleisli)(a);
+            let c_log_bc: WriterMonad<Tc, Tlog> = (wbc.kleisli)(b_log_ab.value);
+            let mut log: Tlog = b_log_ab.log;
+            log.mappend_assign(c_log_bc.log);
+            Writer { value: c_log_bc.value, log }
+        })
+    }
+
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::string::{String, ToString};
+    use crate::{writer_binds, writer_do};
+
+    #[test]
+    fn test_do() {
+        let do_calculation = |x: i64| writer_do!(sync =>
+            log(format!("received number {}", x)),
+            x0 = x + 10,
+            log("added 10 to the number".to_string()),
+            writer_unit(x0)
+        );
+        assert_eq!(do_calculation(5), Writer::new(15, "received number 5\nadded 10 to the number".to_string()));
+    }
+
+    #[test]
+    fn test_compose() {
+        let k0: WriterKleisli<i64, i64, String> = WriterKleisli { kleisli: Arc::new(|x| Writer::new(x + 1, "add".to_string())) };
+        let k1: WriterKleisli<i64, i64, String> = WriterKleisli { kleisli: Arc::new(|x| Writer::new(x * 3, "mul".to_string())) };
+        let k2: WriterKleisli<i64, i64, String> = compose_writers(k0.clone(), k1.clone());
+        assert_eq!(writer_binds!(sync => { writer_unit(1) } >>= { k0 } >>= { k1 }), writer_bind(writer_unit(1), k2));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_threads() {
+        let k0: WriterKleisli<i64, i64, String> = WriterKleisli { kleisli: Arc::new(|x| Writer::new(x + 1, format!("saw {}", x))) };
+        let worker = std::thread::spawn(move || writer_bind(Writer::new(1, "start".to_string()), k0));
+        assert_eq!(worker.join().unwrap(), Writer::new(2, "start\nsaw 1".to_string()));
+    }
+}
@@ -0,0 +1,250 @@
+#[cfg(any(test, feature = "testing"))]
+use core::fmt::Debug;
+
+use super::monoid::Semigroup;
+#[cfg(any(test, feature = "testing"))]
+use super::observe::ObserveEq;
+
+
+// Either a value or every error found while looking for it.
+// unlike `Result`, applying a failed function to a failed argument keeps
+// the errors of both, combined with their semigroup. there is no `bind`:
+// a step that needs the previous value could not run after a failure, so
+// the errors it would have reported would be lost.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Validation<E, Ta> {
+    Failure(E),
+    Success(Ta)
+}
+
+impl<E, Ta> Validation<E, Ta> {
+    pub fn is_success(&self) -> bool { matches!(self, Validation::Success(_)) }
+
+    pub fn into_result(self) -> Result<Ta, E> { self.into() }
+}
+
+impl<E, Ta> From<Result<Ta, E>> for Validation<E, Ta> {
+    fn from(r: Result<Ta, E>) -> Self {
+        match r {
+            Ok(a) => Validation::Success(a),
+            Err(e) => Validation::Failure(e)
+        }
+    }
+}
+
+impl<E, Ta> From<Validation<E, Ta>> for Result<Ta, E> {
+    fn from(v: Validation<E, Ta>) -> Self {
+        match v {
+            Validation::Success(a) => Ok(a),
+            Validation::Failure(e) => Err(e)
+        }
+    }
+}
+
+
+// the outcome is out in the open; there is nothing to run.
+#[cfg(any(test, feature = "testing"))]
+impl<E: PartialEq + Debug + Clone, Ta: PartialEq + Debug + Clone> ObserveEq for Validation<E, Ta> {
+    type Input = ();
+    type Output = Validation<E, Ta>;
+    fn observe(&self, _: &()) -> Self::Output { self.clone() }
+}
+
+
+pub fn validation_unit<E, Ta>(a: Ta) -> Validation<E, Ta> {
+    Validation::Success(a)
+}
+
+pub fn failure<E, Ta>(e: E) -> Validation<E, Ta> {
+    Validation::Failure(e)
+}
+
+
+// functor
+pub fn validation_fmap<E, Ta, Tb>(f_ab: impl FnOnce(Ta) -> Tb, ma: Validation<E, Ta>) -> Validation<E, Tb> {
+    match ma {
+        Validation::Success(a) => Validation::Success(f_ab(a)),
+        Validation::Failure(e) => Validation::Failure(e)
+    }
+}
+
+
+// applicative
+// the errors of `mf` come before those of `ma`.
+pub fn validation_apply<E: Semigroup, Ta, Tb>(
+    mf: Validation<E, impl FnOnce(Ta) -> Tb>,
+    ma: Validation<E, Ta>
+) -> Validation<E, Tb> {
+    match (mf, ma) {
+        (Validation::Success(f_ab), Validation::Success(a)) => Validation::Success(f_ab(a)),
+        (Validation::Success(_), Validation::Failure(e)) => Validation::Failure(e),
+        (Validation::Failure(e), Validation::Success(_)) => Validation::Failure(e),
+        (Validation::Failure(e0), Validation::Failure(e1)) => Validation::Failure(e0.sappend(e1))
+    }
+}
+
+
+// used by `validate!`: keeps the value, or adds the errors to those found so far.
+#[doc(hidden)]
+pub fn collect<E: Semigroup, Ta>(errors: &mut Option<E>, ma: Validation<E, Ta>) -> Option<Ta> {
+    match ma {
+        Validation::Success(a) => Some(a),
+        Validation::Failure(e) => {
+            *errors = Some(match errors.take() {
+                None => e,
+                Some(acc) => acc.sappend(e)
+            });
+            None
+        }
+    }
+}
+
+
+// builds a struct out of validated fields, as in
+//
+//     validate!(Settings { host: check_host(h), port: check_port(p) })
+//
+// every field is validated, in order, before anything is built; the struct
+// is only built if all of them succeeded, otherwise their errors are combined.
+// each field may be a `Validation` or a `Result`.
+#[macro_export]
+macro_rules! validate {
+    ($($name:ident)::+ { $($field:ident : $e:expr),+ $(,)? }) => {{
+        let mut errors = ::core::option::Option::None;
+        let ($($field,)+) = ($(
+            $crate::monads::validation::collect(&mut errors, $crate::monads::validation::Validation::from($e)),
+        )+);
+        match errors {
+            ::core::option::Option::Some(e) => $crate::monads::validation::Validation::Failure(e),
+            ::core::option::Option::None => $crate::monads::validation::Validation::Success(
+                $($name)::+ { $($field: $field.unwrap()),+ }
+            )
+        }
+    }};
+}
+
+
+// tests
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::format;
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use crate::monads::laws::check_functor_laws;
+    use crate::monads::prop::Config;
+
+    #[derive(Clone, Debug, PartialEq)]
+    struct Settings {
+        host: String,
+        port: u16,
+        retries: u8
+    }
+
+    fn check_host(host: &str) -> Validation<Vec<String>, String> {
+        if host.is_empty() { return failure(vec!["host: empty".to_string()]) }
+        validation_unit(host.to_string())
+    }
+
+    fn check_port(port: &str) -> Validation<Vec<String>, u16> {
+        match port.parse::<u16>() {
+            Ok(0) => failure(vec!["port: must not be 0".to_string()]),
+            Ok(p) => validation_unit(p),
+            Err(_) => failure(vec![format!("port: not a number: {:?}", port)])
+        }
+    }
+
+    fn parse_retries(retries: &str) -> Result<u8, Vec<String>> {
+        retries.parse::<u8>().map_err(|_| vec![format!("retries: not a number: {:?}", retries)])
+    }
+
+    fn settings(host: &str, port: &str, retries: &str) -> Validation<Vec<String>, Settings> {
+        validate!(Settings { host: check_host(host), port: check_port(port), retries: parse_retries(retries) })
+    }
+
+    #[test]
+    fn test_validate() {
+        assert_eq!(
+            settings("localhost", "8080", "3"),
+            Validation::Success(Settings { host: "localhost".to_string(), port: 8080, retries: 3 })
+        );
+        assert_eq!(
+            settings("", "http", "-1"),
+            Validation::Failure(vec![
+                "host: empty".to_string(),
+                "port: not a number: \"http\"".to_string(),
+                "retries: not a number: \"-1\"".to_string()
+            ])
+        );
+        assert_eq!(settings("localhost", "0", "3"), failure(vec!["port: must not be 0".to_string()]));
+    }
+
+    #[test]
+    fn test_apply() {
+        let add: Validation<Vec<String>, fn(i64) -> i64> = validation_unit(|x: i64| x + 1);
+        assert_eq!(validation_apply(add, validation_unit(41)), Validation::Success(42));
+
+        let bad_f: Validation<Vec<&str>, fn(i64) -> i64> = failure(vec!["f"]);
+        let bad_a: Validation<Vec<&str>, i64> = failure(vec!["a"]);
+        assert_eq!(validation_apply(bad_f, bad_a), failure(vec!["f", "a"]));
+
+        // errors are combined with any semigroup; strings become lines.
+        let pair = |a: i64| move |b: i64| (a, b);
+        let v: Validation<String, (i64, i64)> = validation_apply(
+            validation_fmap(pair, failure("no a".to_string())),
+            failure("no b".to_string())
+        );
+        assert_eq!(v, failure("no a\nno b".to_string()));
+    }
+
+    #[test]
+    fn test_result() {
+        let ok: Result<i64, String> = Ok(1);
+        assert_eq!(Validation::from(ok.clone()).into_result(), ok);
+        let err: Result<i64, String> = Err("nope".to_string());
+        assert_eq!(Validation::from(err.clone()).into_result(), err);
+        assert!(!Validation::from(err).is_success());
+    }
+
+    // a semigroup with no identity: only the worst error is kept.
+    #[derive(Clone, Debug, PartialEq)]
+    struct Worst(u8);
+
+    impl Semigroup for Worst {
+        fn sappend(self, other: Worst) -> Worst { Worst(self.0.max(other.0)) }
+    }
+
+    #[test]
+    fn test_semigroup() {
+        let m: Validation<Worst, (i64, i64)> = validation_apply(
+            validation_fmap(|a: i64| move |b: i64| (a, b), failure(Worst(3))),
+            failure(Worst(1))
+        );
+        assert_eq!(m, failure(Worst(3)));
+    }
+
+    #[test]
+    fn test_laws() {
+        check_functor_laws(
+            &Config::default(),
+            |p: &(bool, i64)| if p.0 { validation_unit(p.1) } else { failure(vec![p.1]) },
+            |f, m: Validation<Vec<i64>, i64>| validation_fmap(move |a| f(a), m)
+        ).unwrap();
+
+        // the errors found along the way do not depend on how the applications are grouped.
+        let cases: Vec<Validation<Vec<i64>, i64>> = vec![validation_unit(1), failure(vec![2]), failure(vec![3])];
+        for a in &cases {
+            for b in &cases {
+                for c in &cases {
+                    let add = |x: i64| move |y: i64| x + y;
+                    let left: Validation<Vec<i64>, i64> =
+                        validation_apply(validation_fmap(add, validation_apply(validation_fmap(add, a.clone()), b.clone())), c.clone());
+                    let right: Validation<Vec<i64>, i64> =
+                        validation_apply(validation_fmap(add, a.clone()), validation_apply(validation_fmap(add, b.clone()), c.clone()));
+                    assert_eq!(left, right);
+                }
+            }
+        }
+    }
+}
@@ -1,63 +1,143 @@
-use std::fmt::Display;
-use std::rc::Rc;
+#[cfg(any(test, feature = "testing"))]
+use core::fmt::Debug;
+use alloc::rc::Rc;
 
+use super::identity::{Identity, Monad};
 use super::monoid::{Monoid};
+#[cfg(any(test, feature = "testing"))]
+use super::observe::ObserveEq;
 
 
-// A straightforward representation of the writer monad.
-// Any joining of an object and a log [monoid] will suffice.
-pub type WriterMonad<Ta, Tlog> = (Ta, Tlog);
+// A value together with the log [monoid] written while computing it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Writer<Ta, Tlog> {
+    pub value: Ta,
+    pub log: Tlog
+}
+
+// WriterT w m a: a computation in the base monad `M` that also writes a log.
+// there is nothing to defer, so it is the base monad around a `Writer`.
+pub type WriterT<M, Ta, Tlog> = <M as Monad>::M<Writer<Ta, Tlog>>;
+
+// over `Identity` that is the `Writer` itself.
+pub type WriterMonad<Ta, Tlog> = WriterT<Identity, Ta, Tlog>;
 
 
-pub fn writer_unit<Ta: Display + Copy, Tlog: Monoid<T = Tlog>>(a: Ta) -> WriterMonad<Ta, Tlog> {
-    (a, <Tlog as Monoid>::mempty())
+impl<Ta, Tlog> Writer<Ta, Tlog> {
+    pub fn new(value: Ta, log: Tlog) -> Self { Writer { value, log } }
+
+    // runWriter :: Writer w a -> (a, w)
+    pub fn run_writer(self) -> (Ta, Tlog) { (self.value, self.log) }
+}
+
+impl<Ta, Tlog> From<(Ta, Tlog)> for Writer<Ta, Tlog> {
+    fn from((value, log): (Ta, Tlog)) -> Self { Writer { value, log } }
+}
+
+
+// the value and the log are out in the open; there is nothing to run.
+#[cfg(any(test, feature = "testing"))]
+impl<Ta: PartialEq + Debug + Clone, Tlog: PartialEq + Debug + Clone> ObserveEq for Writer<Ta, Tlog> {
+    type Input = ();
+    type Output = Writer<Ta, Tlog>;
+    fn observe(&self, _: &()) -> Self::Output { self.clone() }
+}
+
+
+pub fn writer_unit<Ta, Tlog: Monoid<T = Tlog>>(a: Ta) -> WriterMonad<Ta, Tlog> {
+    Writer { value: a, log: <Tlog as Monoid>::mempty() }
 }
 
 
 // functor
-pub fn writer_fmap<Ta, Tb, Tlog: Monoid>(
-    f_ab: fn(Ta) -> Tb,
+pub fn writer_fmap<Ta, Tb, Tlog>(
+    f_ab: impl FnOnce(Ta) -> Tb,
     ma: WriterMonad<Ta, Tlog>
 ) -> WriterMonad<Tb, Tlog> {
-    (f_ab(ma.0), ma.1)
+    Writer { value: f_ab(ma.value), log: ma.log }
 }
 
 
 // applicative
 pub fn writer_apply<Ta, Tb, Tlog: Monoid<T = Tlog>>(
-    mf: WriterMonad<fn(Ta) -> Tb, Tlog>,
+    mf: WriterMonad<impl FnOnce(Ta) -> Tb, Tlog>,
     ma: WriterMonad<Ta, Tlog>
 ) -> WriterMonad<Tb, Tlog> {
-    ((mf.0)(ma.0), mf.1.mappend(ma.1))
+    let mut log: Tlog = mf.log;
+    log.mappend_assign(ma.log);
+    Writer { value: (mf.value)(ma.value), log }
 }
 
 
 // monad
-#[derive(Clone)]
-pub struct WriterKleisli<Ta, Tb, Tlog: Monoid> {
-    pub kleisli: Rc<dyn Fn(Ta) -> WriterMonad<Tb, Tlog>>
+pub struct WriterTKleisli<M: Monad, Ta, Tb, Tlog: Monoid> {
+    pub kleisli: Rc<dyn Fn(Ta) -> WriterT<M, Tb, Tlog>>
+}
+
+impl<M: Monad, Ta, Tb, Tlog: Monoid> Clone for WriterTKleisli<M, Ta, Tb, Tlog> {
+    fn clone(&self) -> Self { WriterTKleisli { kleisli: self.kleisli.clone() } }
 }
 
+pub type WriterKleisli<Ta, Tb, Tlog> = WriterTKleisli<Identity, Ta, Tb, Tlog>;
+
 pub fn writer_bind<Ta, Tb, Tlog: Monoid<T = Tlog>>(
     ma: WriterMonad<Ta, Tlog>,
     k_ab: WriterKleisli<Ta, Tb, Tlog>
 ) -> WriterMonad<Tb, Tlog> {
-    let b_log_ab: WriterMonad<Tb, Tlog> = (k_ab.kleisli)(ma.0);
-    (b_log_ab.0, ma.1.mappend(b_log_ab.1))
+    let b_log_ab: WriterMonad<Tb, Tlog> = (k_ab.kleisli)(ma.value);
+    let mut log: Tlog = ma.log;
+    log.mappend_assign(b_log_ab.log);
+    Writer { value: b_log_ab.value, log }
 }
 
 
 // pushes a message into the log.
 pub fn log<Tlog: Monoid>(msg: Tlog) -> WriterMonad<(), Tlog> {
-    ((), msg)
+    Writer { value: (), log: msg }
+}
+
+
+// the same operations for any base monad. the plain ones above are kept
+// for `Identity`, where they need no trip through `Monad`. `M` cannot be
+// inferred back from `M::M<..>`, so it is named at each call: `writer_t_log::<M, _>(..)`.
+pub fn writer_t_unit<M: Monad, Ta, Tlog: Monoid<T = Tlog>>(a: Ta) -> WriterT<M, Ta, Tlog> {
+    M::unit(writer_unit(a))
 }
 
+pub fn writer_t_bind<M: Monad, Ta, Tb, Tlog: Monoid<T = Tlog> + Clone>(
+    ma: WriterT<M, Ta, Tlog>,
+    k_ab: WriterTKleisli<M, Ta, Tb, Tlog>
+) -> WriterT<M, Tb, Tlog> {
+    M::bind(ma, |a_log: Writer<Ta, Tlog>| -> WriterT<M, Tb, Tlog> {
+        M::fmap(|b_log: Writer<Tb, Tlog>| -> Writer<Tb, Tlog> {
+            let mut log: Tlog = a_log.log.clone();
+            log.mappend_assign(b_log.log);
+            Writer { value: b_log.value, log }
+        }, (k_ab.kleisli)(a_log.value))
+    })
+}
+
+// tell :: w -> WriterT w m ()
+pub fn writer_t_log<M: Monad, Tlog: Monoid>(msg: Tlog) -> WriterT<M, (), Tlog> {
+    M::unit(log(msg))
+}
 
+// lift :: m a -> WriterT w m a
+pub fn writer_t_lift<M: Monad, Ta, Tlog: Monoid<T = Tlog>>(ma: M::M<Ta>) -> WriterT<M, Ta, Tlog> {
+    M::fmap(writer_unit, ma)
+}
+
+
+// `writer_binds!(sync => ...)` chains the thread-safe kleislis of `monads::sync::writer`.
 #[macro_export]
 macro_rules! writer_binds {
-    ($m:block) => { $m };
-    ($m:block >>= $k:block) => { writer_bind($m, $k) };
-    ($m:block >>= $k:block >>= $($rest:tt)*) => { writer_binds!({ writer_bind($m, $k) } >>= $($rest)* ) };
+    (sync => $($rest:tt)*) => { $crate::writer_binds!(@[$crate::monads::sync::writer] $($rest)*) };
+    (@[$($m:tt)*] $mb:block) => { $mb };
+    (@[$($m:tt)*] $mb:block >>= $k:block) => { $($m)*::writer_bind($mb, $k) };
+    (@[$($m:tt)*] $mb:block >>= $k:block >>= $($rest:tt)*) => {
+        $crate::writer_binds!(@[$($m)*] { $($m)*::writer_bind($mb, $k) } >>= $($rest)* )
+    };
+    ($($rest:tt)*) => { $crate::writer_binds!(@[$crate::monads::writer] $($rest)*) };
 }
 
 
@@ -69,64 +149,97 @@ pub fn compose_writers<Ta: 'static, Tb: 'static, Tc: 'static, Tlog: Monoid<T = T
     WriterKleisli {
         kleisli: Rc::new(move |a: Ta| -> WriterMonad<Tc, Tlog> {
             let b_log_ab: WriterMonad<Tb, Tlog> = (wab.kleisli)(a);
-            let c_log_bc: WriterMonad<Tc, Tlog> = (wbc.kleisli)(b_log_ab.0);
-            (c_log_bc.0, b_log_ab.1.mappend(c_log_bc.1))
+            let c_log_bc: WriterMonad<Tc, Tlog> = (wbc.kleisli)(b_log_ab.value);
+            let mut log: Tlog = b_log_ab.log;
+            log.mappend_assign(c_log_bc.log);
+            Writer { value: c_log_bc.value, log }
         })
     }
 
 }
 
 
+// do-notation. `writer_do!(sync => ...)` builds the thread-safe variant
+// from `monads::sync::writer` instead, and `writer_do!(lazy => ...)` the one
+// from `monads::lazy::writer` whose log is only built when it is read.
+// internally every rule carries the module to take the monad from and the
+// pointer its kleisli arrows are stored in.
 #[macro_export]
 macro_rules! writer_do {
+    (sync => $($rest:tt)*) => { $crate::writer_do!(@[$crate::monads::sync::writer][$crate::monads::sync::Arc] $($rest)*) };
+    (lazy => $($rest:tt)*) => { $crate::writer_do!(@[$crate::monads::lazy::writer][$crate::prelude::Rc] $($rest)*) };
+
     // trailing comma
-    () => {};
+    (@[$($m:tt)*][$($p:tt)*]) => {};
 
-    ($v:ident = $e:expr,  $($rest:tt)*) => { (|$v| { writer_do!($($rest)*) })($e) };
+    (@[$($m:tt)*][$($p:tt)*] $v:ident = $e:expr,  $($rest:tt)*) => { (|$v| { $crate::writer_do!(@[$($m)*][$($p)*] $($rest)*) })($e) };
 
-    ($v:ident <- $e:expr, $($rest:tt)*) => {
-        writer_bind(
+    (@[$($m:tt)*][$($p:tt)*] $v:ident <- $e:expr, $($rest:tt)*) => {
+        $($m)*::writer_bind(
             $e,
-            WriterKleisli {
-                kleisli: Rc::new( move |$v| { writer_do!($($rest)*) } )
+            $($m)*::WriterKleisli {
+                kleisli: $($p)*::new( move |$v| { $crate::writer_do!(@[$($m)*][$($p)*] $($rest)*) } )
             }
         )
     };
 
-    ($e:expr, $($rest:tt)*) => {
-        writer_bind(
+    (@[$($m:tt)*][$($p:tt)*] $e:expr, $($rest:tt)*) => {
+        $($m)*::writer_bind(
             $e,
-            WriterKleisli {
-                kleisli: Rc::new( move |_| { writer_do!($($rest)*) } )
+            $($m)*::WriterKleisli {
+                kleisli: $($p)*::new( move |_| { $crate::writer_do!(@[$($m)*][$($p)*] $($rest)*) } )
             }
         )
     };
 
-    ($e:expr) => { $e };
+    (@[$($m:tt)*][$($p:tt)*] $e:expr) => { $e };
+
+    ($($rest:tt)*) => { $crate::writer_do!(@[$crate::monads::writer][$crate::prelude::Rc] $($rest)*) };
 }
 
 
+
 // tests
 #[cfg(test)]
 mod tests {
     use super::*;
+    use alloc::format;
+    use alloc::string::{String, ToString};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use crate::monads::laws::{check_functor_laws, check_monad_laws};
+    use crate::monads::prop::Config;
+    use crate::monads::identity::tests::Maybe;
 
     #[test]
     fn test_fmap() {
         assert_eq!(
-            writer_fmap(|x| 2 * x, (5, "hello".to_string())),
-            (10, "hello".to_string()));
+            writer_fmap(|x| 2 * x, Writer::new(5, "hello".to_string())),
+            Writer::new(10, "hello".to_string()));
+        assert_eq!(
+            writer_fmap(|x| if x == 5 { "zero" } else { "one" }, Writer::new(5, "hello".to_string())),
+            Writer::new("zero", "hello".to_string())
+        );
+
+        // closures may capture, and by move.
+        let suffix: String = "!".to_string();
         assert_eq!(
-            writer_fmap(|x| if x == 5 { "zero" } else { "one" }, (5, "hello".to_string())),
-            ("zero", "hello".to_string())
+            writer_fmap(move |x: String| x + &suffix, Writer::new("hi".to_string(), vec![1])).run_writer(),
+            ("hi!".to_string(), vec![1])
         );
     }
 
     #[test]
     fn test_apply() {
         assert_eq!(
-            writer_apply((|x| 2*x, "hello".to_string()), (3, "goodbye".to_string())),
-            (6, "hello\ngoodbye".to_string())
+            writer_apply(Writer::new(|x| 2*x, "hello".to_string()), Writer::new(3, "goodbye".to_string())),
+            Writer::new(6, "hello\ngoodbye".to_string())
+        );
+
+        let k: i64 = 7;
+        assert_eq!(
+            writer_apply(Writer::new(move |x| k * x, "hello".to_string()), Writer::new(3, "goodbye".to_string())),
+            Writer::new(21, "hello\ngoodbye".to_string())
         );
     }
 
@@ -134,10 +247,10 @@ mod tests {
     fn test_bind() {
         assert_eq!(
             writer_bind(
-                (1, "hello".to_string()),
-                WriterKleisli { kleisli: Rc::new( |x| (2*x, "goodbye".to_string())) }
+                Writer::new(1, "hello".to_string()),
+                WriterKleisli { kleisli: Rc::new( |x| Writer::new(2*x, "goodbye".to_string())) }
             ),
-            (2, "hello\ngoodbye".to_string())
+            Writer::new(2, "hello\ngoodbye".to_string())
         );
     }
 
@@ -152,8 +265,48 @@ mod tests {
             writer_unit(x1)
         );
         assert_eq!(
-            do_calculation(5),
-            (30, "received number 5\nadded 10 to the number\nmultiplied result by 2\n".to_string())
+            do_calculation(5).run_writer(),
+            (30, "received number 5\nadded 10 to the number\nmultiplied result by 2".to_string())
+        );
+    }
+
+    #[test]
+    fn test_transformer() {
+        let parse = |input: &'static str| -> WriterT<Maybe, i64, String> {
+            writer_t_bind::<Maybe, _, _, _>(
+                writer_t_log::<Maybe, _>(format!("parsing {}", input)),
+                WriterTKleisli { kleisli: Rc::new(move |_| writer_t_lift::<Maybe, _, _>(input.parse::<i64>().ok())) }
+            )
+        };
+        assert_eq!(parse("12"), Some(Writer::new(12, "parsing 12".to_string())));
+        assert_eq!(parse("twelve"), None);
+
+        // over `Identity` the transformer forms are the plain monad.
+        let w: WriterMonad<i64, Vec<i64>> = writer_t_bind::<Identity, _, _, _>(
+            writer_t_unit::<Identity, _, _>(1),
+            WriterKleisli { kleisli: Rc::new(|x: i64| Writer::new(x + 1, vec![x])) }
         );
+        assert_eq!(w, Writer::new(2, vec![1]));
+    }
+
+    #[test]
+    fn test_laws() {
+        check_functor_laws(
+            &Config::default(),
+            |p: &(i64, String)| Writer::from(p.clone()),
+            |f, m| writer_fmap(move |a| f(a), m)
+        ).unwrap();
+        check_monad_laws(
+            &Config::default(),
+            |p: &(i64, String)| Writer::from(p.clone()),
+            writer_unit,
+            |m, k| writer_bind(m, WriterKleisli { kleisli: k })
+        ).unwrap();
+        check_monad_laws(
+            &Config::default(),
+            |p: &(i64, Vec<i64>)| Writer::from(p.clone()),
+            writer_unit,
+            |m, k| writer_bind(m, WriterKleisli { kleisli: k })
+        ).unwrap();
     }
 }
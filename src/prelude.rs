@@ -0,0 +1,148 @@
+// use monad_rs::prelude::*;
+//
+// brings every enabled monad into scope together with its do-notation.
+// `Rc` is included since kleisli arrows and functorial maps are built from it.
+
+#[cfg(feature = "alloc")]
+pub use alloc::rc::Rc;
+
+pub use crate::monads::monoid::{Monoid, Semigroup, fold_map};
+#[cfg(feature = "std")]
+pub use crate::monads::monoid::fold_map_par;
+
+#[cfg(feature = "alloc")]
+pub use crate::monads::identity::{Monad, Identity, IdentityKleisli, identity_unit, identity_fmap, identity_apply, identity_bind};
+#[cfg(feature = "alloc")]
+pub use crate::identity_do;
+
+#[cfg(feature = "reader")]
+pub use crate::monads::reader::{ReaderMonad, ReaderKleisli, reader_unit, reader_fmap, reader_apply, reader_bind, load, asks, reader_owned, run_reader};
+#[cfg(feature = "reader")]
+pub use crate::monads::reader::{ReaderT, ReaderTKleisli, reader_t_unit, reader_t_bind, reader_t_asks, reader_t_lift};
+#[cfg(feature = "reader")]
+pub use crate::reader_do;
+
+#[cfg(feature = "state")]
+pub use crate::monads::state::{StateMonad, StateKleisli, state_unit, state_fmap, state_apply, state_bind, get, put};
+#[cfg(feature = "state")]
+pub use crate::monads::state::{StateT, StateTKleisli, state_t_unit, state_t_bind, state_t, state_t_lift};
+#[cfg(feature = "state")]
+pub use crate::state_do;
+
+#[cfg(feature = "writer")]
+pub use crate::monads::writer::{Writer, WriterMonad, WriterKleisli, writer_unit, writer_fmap, writer_apply, writer_bind, compose_writers, log};
+#[cfg(feature = "writer")]
+pub use crate::monads::writer::{WriterT, WriterTKleisli, writer_t_unit, writer_t_bind, writer_t_log, writer_t_lift};
+#[cfg(feature = "writer")]
+pub use crate::{writer_do, writer_binds};
+
+#[cfg(feature = "cont")]
+pub use crate::monads::cont::{ContMonad, ContKleisli, cont_unit, cont_eval, cont_fmap, cont_apply, cont_bind};
+#[cfg(feature = "cont")]
+pub use crate::cont_do;
+
+// `perform` is left to `monads::effect::perform`, the prelude's is the operational one.
+#[cfg(feature = "effects")]
+pub use crate::monads::effect::{Effect, Eff, Effectful, Resume, ResumeWith, run_eff, handle, handle_with};
+#[cfg(feature = "effects")]
+pub use crate::monads::effect::{get_state, put_state, handle_state, ask_env, handle_reader, tell, handle_writer, raise, handle_exception, choose, fail, handle_choice};
+
+#[cfg(feature = "generators")]
+pub use crate::monads::generator::{Gen, Generator, yield_, generator, Co, CoState, Coroutine, suspend, coroutine, Sym, SymBody, transfer, run_symmetric};
+
+// `fail` is left to `monads::amb::fail`, the prelude's is the effect one.
+#[cfg(feature = "amb")]
+pub use crate::monads::amb::{Amb, Stream, Solutions, amb, amb_fair, either, either_fair, require, solutions};
+
+#[cfg(feature = "logic")]
+pub use crate::monads::logic::{LogicBase, LogicT, Logic, LogicTKleisli, LogicKleisli, Split, First, logic_unit, logic_empty, logic_guard, logic_lift, logic_plus, logic_fmap, logic_bind};
+#[cfg(feature = "logic")]
+pub use crate::monads::logic::{interleave, fair_bind, msplit, reflect, once, ifte, observe_many, observe_all};
+#[cfg(all(feature = "logic", feature = "state"))]
+pub use crate::monads::logic::StateBase;
+#[cfg(feature = "logic")]
+pub use crate::logic_do;
+
+#[cfg(feature = "scheduler")]
+pub use crate::monads::scheduler::{Task, Action, JoinHandle, Channel, yield_now, spawn, join, channel, send, recv, run_tasks};
+
+#[cfg(feature = "exceptions")]
+pub use crate::monads::exception::{Exc, OnError, Catching, throw, try_catch, finally, run_exc};
+
+#[cfg(feature = "cps")]
+pub use crate::monads::cps::{Cps, CpsKleisli, Step, cps_unit, cps_fmap, cps_apply, cps_bind, defer, call, run_cps, recursive, run_frames};
+#[cfg(feature = "cps")]
+pub use crate::cps_do;
+
+#[cfg(feature = "future")]
+pub use crate::monads::future::{BoxFuture, boxed, block_on};
+#[cfg(feature = "future")]
+pub use crate::monads::future::{AsyncState, AsyncStateKleisli, async_state_unit, async_state_fmap, async_state_apply, async_state_bind, async_state_lift, async_get, async_put};
+#[cfg(feature = "future")]
+pub use crate::monads::future::{AsyncReader, AsyncReaderKleisli, async_reader_unit, async_reader_fmap, async_reader_apply, async_reader_bind, async_reader_lift, async_asks, run_async_reader};
+#[cfg(feature = "future")]
+pub use crate::monads::future::{AsyncWriter, AsyncWriterKleisli, async_writer_unit, async_writer_fmap, async_writer_apply, async_writer_bind, async_writer_lift, async_log};
+#[cfg(feature = "future")]
+pub use crate::async_do;
+
+#[cfg(feature = "io")]
+pub use crate::monads::io::{IO, IOKleisli, LiftIO, unsafe_run, delay, io_unit, io_fmap, io_apply, io_bind, bracket, lift_io};
+#[cfg(all(feature = "io", feature = "writer"))]
+pub use crate::monads::io::{WriterIO, writer_io_unit, writer_io_bind, writer_io_log};
+#[cfg(feature = "io")]
+pub use crate::io_do;
+
+#[cfg(feature = "free")]
+pub use crate::monads::free::{Functor, CloneFunctor, Free, FreeKleisli, free_unit, free_fmap, free_apply, free_bind, lift_f, fold_free};
+#[cfg(feature = "free")]
+pub use crate::free_do;
+#[cfg(feature = "free_ap")]
+pub use crate::monads::free_ap::{FreeAp, free_ap_unit, free_ap_fmap, free_ap_apply, lift_ap, analyze, run_ap};
+#[cfg(all(feature = "free_ap", feature = "reader"))]
+pub use crate::monads::free_ap::free_ap_reader;
+
+#[cfg(feature = "operational")]
+pub use crate::monads::operational::{Program, ProgramKleisli, Interpreter, perform, program_unit, program_fmap, program_apply, program_bind, run_program};
+#[cfg(feature = "operational")]
+pub use crate::program_do;
+
+#[cfg(any(feature = "operational", feature = "free_ap"))]
+pub use crate::monads::instr::{Instr, Answer};
+
+#[cfg(feature = "validation")]
+pub use crate::monads::validation::{Validation, validation_unit, validation_fmap, validation_apply, failure};
+#[cfg(feature = "validation")]
+pub use crate::validate;
+
+
+// use monad_rs::prelude::sync::*;
+//
+// the same, with the thread-safe monads. the do-notation targets them with
+// a leading `sync =>`, as in `state_do!(sync => ...)`.
+#[cfg(feature = "sync")]
+pub mod sync {
+    pub use crate::monads::monoid::{Monoid, Semigroup, fold_map};
+    #[cfg(feature = "std")]
+    pub use crate::monads::monoid::fold_map_par;
+    pub use crate::monads::sync::Arc;
+
+    #[cfg(feature = "reader")]
+    pub use crate::monads::sync::reader::{ReaderMonad, ReaderKleisli, reader_unit, reader_fmap, reader_apply, reader_bind, load, asks, reader_owned, run_reader};
+    #[cfg(feature = "reader")]
+    pub use crate::reader_do;
+
+    #[cfg(feature = "state")]
+    pub use crate::monads::sync::state::{StateMonad, StateKleisli, state_unit, state_fmap, state_apply, state_bind, get, put};
+    #[cfg(feature = "state")]
+    pub use crate::state_do;
+
+    #[cfg(feature = "writer")]
+    pub use crate::monads::sync::writer::{Writer, WriterMonad, WriterKleisli, writer_unit, writer_fmap, writer_apply, writer_bind, compose_writers, log};
+    #[cfg(feature = "writer")]
+    pub use crate::{writer_do, writer_binds};
+
+    #[cfg(feature = "cont")]
+    pub use crate::monads::sync::cont::{ContMonad, ContKleisli, cont_unit, cont_eval, cont_fmap, cont_apply, cont_bind};
+    #[cfg(feature = "cont")]
+    pub use crate::cont_do;
+}